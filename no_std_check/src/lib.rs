@@ -0,0 +1,20 @@
+#![no_std]
+
+// Builds the graph core the way a `no_std` user would. `std` has a panic handler of its own, so if
+// anything pulls it in again this fails with a duplicate `panic_impl` lang item.
+
+extern crate alloc;
+
+use core::panic::PanicInfo;
+use graphs::{create_graph, graph::Graph};
+
+pub fn chain() -> Graph {
+    let mut graph = create_graph! { 1 => 2; 2 => 3 };
+    graph.connect(3, 4);
+    graph
+}
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    loop {}
+}
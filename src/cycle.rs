@@ -1,13 +1,20 @@
-use core::fmt;
+use alloc::{
+    collections::BTreeSet,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{fmt, str::FromStr};
 
 use itertools::Itertools;
-use std::collections::BTreeSet;
 
+use crate::graph::{EdgeList, Graph, NodeId};
+
+// A ring of at least two distinct nodes, stored starting at its smallest node.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Cycle(Vec<u32>);
+pub struct Cycle(Vec<NodeId>);
 
 impl Cycle {
-    fn rotate(cycle: &mut [u32], by: usize) {
+    fn rotate(cycle: &mut [NodeId], by: usize) {
         let len = cycle.len();
         let by = (by % len + len) % len;
 
@@ -16,7 +23,7 @@ impl Cycle {
         cycle[by..].copy_from_slice(&tmp[..len - by]);
     }
 
-    pub fn new(mut nodes: Vec<u32>) -> Option<Self> {
+    pub fn new(mut nodes: Vec<NodeId>) -> Option<Self> {
         let only_unique = {
             let unique = nodes.iter().cloned().collect::<BTreeSet<_>>();
             unique.len() == nodes.len()
@@ -32,11 +39,52 @@ impl Cycle {
         Some(Self(nodes))
     }
 
+    // skips every check, so tests can build cycles `new` would refuse
+    #[cfg(test)]
+    pub(crate) fn unchecked(nodes: Vec<NodeId>) -> Self {
+        Self(nodes)
+    }
+
+    // rotated so the smallest node comes first, so two cycles are equal iff they're the same ring,
+    // whatever node they started at
+    pub fn normalized(nodes: &[NodeId]) -> Option<Self> {
+        Self::new(nodes.to_vec())
+    }
+
+    // the nodes starting at `start` instead of the smallest one, a `Cycle` itself always starts at
+    // its smallest node so this can't be one
+    pub fn rotated_to_start(&self, start: NodeId) -> Option<Vec<NodeId>> {
+        let pos = self.0.iter().position(|n| *n == start)?;
+
+        let mut nodes = self.0.clone();
+        Self::rotate(&mut nodes, self.len() - pos);
+        Some(nodes)
+    }
+
+    // the edges of the cycle that `graph` doesn't have, in the order of the cycle
+    pub fn edges_missing_in(&self, graph: &Graph) -> EdgeList {
+        self.0
+            .iter()
+            .copied()
+            .circular_tuple_windows()
+            .filter(|(from, to)| {
+                !graph
+                    .from(*from)
+                    .is_some_and(|connected| connected.contains(to))
+            })
+            .collect()
+    }
+
     pub fn len(&self) -> usize {
         self.0.len()
     }
 
-    pub fn slice(&self) -> &[u32] {
+    // a cycle always has at least two nodes
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn slice(&self) -> &[NodeId] {
         &self.0
     }
 }
@@ -55,10 +103,128 @@ impl fmt::Debug for Cycle {
     }
 }
 
+// `1 -> 2 -> 3 -> 1`, the ring is closed explicitly
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in self.0.iter() {
+            write!(f, "{node} -> ")?;
+        }
+        write!(f, "{}", self.0[0])
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CycleError {
+    // fewer than two nodes
+    TooShort,
+    DuplicateNode(NodeId),
+    MissingEdge(NodeId, NodeId),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseCycleError {
+    InvalidNode(String),
+    NotClosed,
+    // repeated nodes or too short
+    Invalid,
+}
+
+impl FromStr for Cycle {
+    type Err = ParseCycleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut nodes = s
+            .split("->")
+            .map(|node| {
+                let node = node.trim();
+                node.parse()
+                    .map_err(|_| ParseCycleError::InvalidNode(node.to_string()))
+            })
+            .collect::<Result<Vec<NodeId>, _>>()?;
+
+        if nodes.len() < 2 || nodes.first() != nodes.last() {
+            return Err(ParseCycleError::NotClosed);
+        }
+        nodes.pop();
+
+        Cycle::new(nodes).ok_or(ParseCycleError::Invalid)
+    }
+}
+
 macro_rules! create_cycle {
     ($i:expr => $($o:expr)=>+) => {{
-        $crate::cycle::Cycle::new(vec![$i, $($o),+]).expect("invalid cycle")
+        $crate::cycle::Cycle::new(alloc::vec![$i, $($o),+]).expect("invalid cycle")
     }}
 }
 
 pub(crate) use create_cycle;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::create_graph;
+    use alloc::vec;
+
+    #[test]
+    fn never_empty() {
+        let cycle = Cycle::new(vec![2, 1]).unwrap();
+
+        assert_eq!(cycle.len(), 2);
+        assert!(!cycle.is_empty());
+        assert_eq!(Cycle::new(vec![1]), None);
+        assert_eq!(Cycle::new(vec![]), None);
+    }
+
+    #[test]
+    fn normalized_rotations_are_equal() {
+        assert_eq!(Cycle::normalized(&[3, 1, 2]), Cycle::normalized(&[1, 2, 3]));
+        assert_eq!(Cycle::normalized(&[3, 1, 2]).unwrap().slice(), &[1, 2, 3]);
+        assert_ne!(Cycle::normalized(&[3, 2, 1]), Cycle::normalized(&[1, 2, 3]));
+        assert_eq!(Cycle::normalized(&[1, 2, 1]), None);
+        assert_eq!(Cycle::normalized(&[1]), None);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let cycle = Cycle::new(vec![3, 4, 1, 2]).unwrap();
+
+        assert_eq!(cycle.to_string(), "1 -> 2 -> 3 -> 4 -> 1");
+        assert_eq!("1 -> 2 -> 3 -> 4 -> 1".parse(), Ok(cycle.clone()));
+        assert_eq!("3 -> 4 -> 1 -> 2 -> 3".parse(), Ok(cycle));
+    }
+
+    #[test]
+    fn malformed_text() {
+        assert_eq!("1 -> 2".parse::<Cycle>(), Err(ParseCycleError::NotClosed));
+        assert_eq!("1".parse::<Cycle>(), Err(ParseCycleError::NotClosed));
+        assert_eq!(
+            "1 -> x -> 1".parse::<Cycle>(),
+            Err(ParseCycleError::InvalidNode("x".to_string()))
+        );
+        assert_eq!(
+            "1 -> 2 -> 2 -> 1".parse::<Cycle>(),
+            Err(ParseCycleError::Invalid)
+        );
+    }
+
+    #[test]
+    fn rotated_to_start() {
+        let cycle = Cycle::new(vec![1, 2, 3, 4]).unwrap();
+
+        assert_eq!(cycle.rotated_to_start(1), Some(vec![1, 2, 3, 4]));
+        assert_eq!(cycle.rotated_to_start(2), Some(vec![2, 3, 4, 1]));
+        assert_eq!(cycle.rotated_to_start(3), Some(vec![3, 4, 1, 2]));
+        assert_eq!(cycle.rotated_to_start(4), Some(vec![4, 1, 2, 3]));
+        assert_eq!(cycle.rotated_to_start(5), None);
+    }
+
+    #[test]
+    fn edges_missing_in() {
+        let mut graph = create_graph! { 1 => 2; 2 => 3; 3 => 1 };
+        let cycle = Cycle::new(vec![1, 2, 3]).unwrap();
+        assert!(cycle.edges_missing_in(&graph).is_empty());
+
+        graph.disconnect(2, 3);
+        assert_eq!(cycle.edges_missing_in(&graph), vec![(2, 3)]);
+    }
+}
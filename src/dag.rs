@@ -0,0 +1,90 @@
+use crate::{
+    cycle::Cycle,
+    graph::{Graph, NodeId},
+};
+use alloc::{collections::BTreeMap, vec::Vec};
+
+// Rejects every edge that would close a cycle, so the `Dag` it builds is known to be acyclic.
+pub struct DagBuilder {
+    graph: Graph,
+}
+
+impl Default for DagBuilder {
+    fn default() -> Self {
+        DagBuilder {
+            graph: Graph::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl DagBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_node(&mut self, id: NodeId) {
+        self.graph.add_node(id);
+    }
+
+    pub fn connect(&mut self, from: NodeId, to: NodeId) -> Result<(), Cycle> {
+        self.graph.connect_checked(from, to)
+    }
+
+    // the graph is trusted not to have cycles, so nothing has to look for them again
+    pub fn build(mut self) -> Dag {
+        self.graph.trust_as_dag();
+        Dag(self.graph)
+    }
+}
+
+// A graph without cycles, only `DagBuilder` can make one.
+pub struct Dag(Graph);
+
+impl Dag {
+    // layer by layer, so nodes without any ordering between them are ascending
+    pub fn topological_sort(&self) -> Vec<NodeId> {
+        self.0.acyclic_layers().into_iter().flatten().collect()
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.0
+    }
+
+    pub fn into_graph(self) -> Graph {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn topological_sort() {
+        let mut builder = DagBuilder::new();
+        builder.connect(3, 1).unwrap();
+        builder.connect(1, 2).unwrap();
+        builder.connect(3, 2).unwrap();
+        builder.add_node(0);
+
+        let dag = builder.build();
+        let order: Vec<NodeId> = dag.topological_sort();
+
+        assert_eq!(order, vec![0, 3, 1, 2]);
+        assert!(dag.graph().is_trusted_dag());
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let mut builder = DagBuilder::new();
+        builder.connect(1, 2).unwrap();
+        builder.connect(2, 3).unwrap();
+
+        assert_eq!(
+            builder.connect(3, 1),
+            Err(Cycle::new(vec![1, 2, 3]).unwrap())
+        );
+        assert_eq!(builder.build().graph().edge_count(), 2);
+    }
+}
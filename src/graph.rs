@@ -1,44 +1,338 @@
-use core::fmt;
-use itertools::Itertools;
-use std::{
-    collections::{BTreeMap, BTreeSet},
-    iter,
+use alloc::{
+    collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque},
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
 };
+use core::{cmp::Reverse, fmt, iter, ops::Index};
+use itertools::Itertools;
+
+mod bytes;
+#[cfg(feature = "std")]
+mod csv;
+mod cycles;
+#[cfg(feature = "std")]
+mod dot;
+mod indexed;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod load;
+mod mermaid;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod walk;
+
+pub use bytes::BytesError;
+#[cfg(feature = "std")]
+pub use csv::CsvError;
+#[cfg(feature = "std")]
+pub use dot::DotError;
+pub use indexed::IndexedGraph;
+#[cfg(feature = "std")]
+pub use json::JsonError;
+#[cfg(feature = "std")]
+pub use load::{Format, LoadError};
+pub use walk::{Walk, WalkError};
+
+// ids are 32 bits wide unless the `u64-ids` feature is enabled, which allows ids past `u32::MAX`
+// at the cost of doubling the memory taken by every stored id
+#[cfg(not(feature = "u64-ids"))]
+pub type NodeId = u32;
+#[cfg(feature = "u64-ids")]
+pub type NodeId = u64;
+
+pub type EdgeList = Vec<(NodeId, NodeId)>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TreeError {
+    MultipleParents(NodeId),
+    Cycle(Cycle),
+}
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConnectOutcome {
+    Added,
+    AlreadyPresent,
+    SelfLoopRejected,
+}
+
+// Everything is kept in ordered collections, so iteration and every format written from a graph
+// only depend on its nodes and edges, never on the order they were added in.
+#[derive(Clone)]
 pub struct Graph {
-    nodes: BTreeMap<u32, BTreeSet<u32>>,
+    nodes: BTreeMap<NodeId, BTreeSet<NodeId>>,
+    // sparse, edges without an entry weigh 1
+    weights: BTreeMap<(NodeId, NodeId), u64>,
+    // how many times an edge was added with `connect_counted`, sparse the same way as the weights
+    multiplicities: BTreeMap<(NodeId, NodeId), u32>,
+    // set by `new_trusted_dag` so cycle checks can be skipped, cleared once an edge closes a cycle
+    trusted_dag: bool,
+}
+
+// whether the graph is a trusted dag doesn't matter
+impl PartialEq for Graph {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes
+            && self.weights == other.weights
+            && self.multiplicities == other.multiplicities
+    }
 }
 
+impl Eq for Graph {}
+
 impl Graph {
-    pub fn new(nodes: BTreeMap<u32, BTreeSet<u32>>) -> Self {
-        Graph { nodes }
+    // same as `from_map_unchecked`, the map is trusted as is
+    pub fn new(nodes: BTreeMap<NodeId, BTreeSet<NodeId>>) -> Self {
+        Self::from_map_unchecked(nodes)
+    }
+
+    // every target has to be a key and no node may point to itself, otherwise the offending id is
+    // returned
+    pub fn from_map(nodes: BTreeMap<NodeId, BTreeSet<NodeId>>) -> Result<Self, NodeId> {
+        for (from, connected) in nodes.iter() {
+            if connected.contains(from) {
+                return Err(*from);
+            }
+
+            if let Some(dangling) = connected.iter().find(|to| !nodes.contains_key(to)) {
+                return Err(*dangling);
+            }
+        }
+
+        Ok(Self::from_map_unchecked(nodes))
+    }
+
+    pub fn from_map_unchecked(nodes: BTreeMap<NodeId, BTreeSet<NodeId>>) -> Self {
+        Graph {
+            nodes,
+            weights: BTreeMap::new(),
+            multiplicities: BTreeMap::new(),
+            trusted_dag: false,
+        }
+    }
+
+    // the map has to be acyclic, that isn't checked, and cycle checks are skipped from then on
+    pub fn new_trusted_dag(nodes: BTreeMap<NodeId, BTreeSet<NodeId>>) -> Self {
+        Graph {
+            trusted_dag: true,
+            ..Self::from_map_unchecked(nodes)
+        }
+    }
+
+    pub fn is_trusted_dag(&self) -> bool {
+        self.trusted_dag
+    }
+
+    // for graphs that were kept acyclic while they were built, like the ones in a `Dag`
+    pub(crate) fn trust_as_dag(&mut self) {
+        self.trusted_dag = true;
+    }
+
+    // `find_cycle`, unless the graph is trusted not to have any
+    fn find_untrusted_cycle(&self) -> Option<Cycle> {
+        if self.trusted_dag {
+            return None;
+        }
+
+        self.find_cycle()
+    }
+
+    // also returns how many edges were dropped for being duplicates or self-loops
+    pub fn from_edge_list_counting(edges: &[(NodeId, NodeId)]) -> (Self, usize) {
+        let mut graph = Graph::new(BTreeMap::new());
+        let mut ignored = 0;
+
+        for (from, to) in edges.iter() {
+            if from == to {
+                ignored += 1;
+                continue;
+            }
+
+            graph.with_node(*to);
+            if !graph.with_node(*from).insert(*to) {
+                ignored += 1;
+            }
+        }
+
+        (graph, ignored)
+    }
+
+    // reads back what `Debug` prints, in either the compact or the alternate form
+    pub fn from_debug_str(s: &str) -> Option<Self> {
+        let body = s
+            .trim()
+            .strip_prefix("Graph")?
+            .trim_start()
+            .strip_prefix('{')?
+            .strip_suffix('}')?;
+
+        let mut nodes = BTreeMap::new();
+        for line in body
+            .split(';')
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            let (node, connected) = line.split_once("=>")?;
+            let connected = connected
+                .split(',')
+                .map(str::trim)
+                .filter(|to| !to.is_empty())
+                .map(|to| to.parse().ok())
+                .collect::<Option<BTreeSet<NodeId>>>()?;
+
+            if nodes.insert(node.trim().parse().ok()?, connected).is_some() {
+                return None;
+            }
+        }
+
+        Self::from_map(nodes).ok()
+    }
+
+    // isolated nodes to `connect` afterwards
+    pub fn with_nodes(ids: impl IntoIterator<Item = NodeId>) -> Self {
+        Graph::new(ids.into_iter().map(|id| (id, BTreeSet::new())).collect())
     }
 
-    fn with_node(&mut self, id: u32) -> &mut BTreeSet<u32> {
+    fn with_node(&mut self, id: NodeId) -> &mut BTreeSet<NodeId> {
         self.nodes.entry(id).or_insert_with(|| BTreeSet::new())
     }
 
-    pub fn connect(&mut self, from: u32, to: u32) -> bool {
+    pub fn add_node(&mut self, id: NodeId) {
+        self.with_node(id);
+    }
+
+    pub fn connect(&mut self, from: NodeId, to: NodeId) -> bool {
         // we don't support cycles of len < 2
+        self.connect_reporting(from, to) != ConnectOutcome::SelfLoopRejected
+    }
+
+    // same as `connect`, but tells apart why nothing was added
+    pub fn connect_reporting(&mut self, from: NodeId, to: NodeId) -> ConnectOutcome {
         if from == to {
-            return false;
+            return ConnectOutcome::SelfLoopRejected;
         }
 
         self.with_node(to);
-        self.with_node(from).insert(to);
+        if self.with_node(from).insert(to) {
+            if self.trusted_dag && self.is_reachable(to, from) {
+                self.trusted_dag = false;
+            }
+
+            ConnectOutcome::Added
+        } else {
+            ConnectOutcome::AlreadyPresent
+        }
+    }
+
+    // like `1 => 2, 3` in `create_graph!`, returns how many edges are new
+    pub fn connect_fanout(&mut self, from: NodeId, tos: impl IntoIterator<Item = NodeId>) -> usize {
+        let mut added = 0;
+
+        for to in tos.into_iter() {
+            if self.connect_reporting(from, to) == ConnectOutcome::Added {
+                added += 1;
+            }
+        }
+
+        added
+    }
+
+    // the other way around from `connect_fanout`, like `1, 2 => 3` in `create_graph!`
+    pub fn connect_fanin(&mut self, froms: impl IntoIterator<Item = NodeId>, to: NodeId) -> usize {
+        let mut added = 0;
+
+        for from in froms.into_iter() {
+            if self.connect_reporting(from, to) == ConnectOutcome::Added {
+                added += 1;
+            }
+        }
+
+        added
+    }
+
+    pub fn connect_weighted(&mut self, from: NodeId, to: NodeId, weight: u64) -> bool {
+        if !self.connect(from, to) {
+            return false;
+        }
+
+        self.set_weight(from, to, weight);
 
         true
     }
 
-    pub fn disconnect(&mut self, from: u32, to: u32) {
+    // the default weight isn't stored, so equal graphs stay equal however they were built
+    fn set_weight(&mut self, from: NodeId, to: NodeId, weight: u64) {
+        if weight == 1 {
+            self.weights.remove(&(from, to));
+        } else {
+            self.weights.insert((from, to), weight);
+        }
+    }
+
+    pub fn weight(&self, from: NodeId, to: NodeId) -> Option<u64> {
+        if !self.from(from)?.contains(&to) {
+            return None;
+        }
+
+        Some(self.weights.get(&(from, to)).copied().unwrap_or(1))
+    }
+
+    // connecting an edge that's already there counts it again instead of doing nothing
+    pub fn connect_counted(&mut self, from: NodeId, to: NodeId) -> bool {
+        if self.connect_reporting(from, to) == ConnectOutcome::AlreadyPresent {
+            *self.multiplicities.entry((from, to)).or_insert(1) += 1;
+        }
+
+        from != to
+    }
+
+    // stored sparsely, the same way as `set_weight`
+    fn set_multiplicity(&mut self, from: NodeId, to: NodeId, count: u32) {
+        if count == 1 {
+            self.multiplicities.remove(&(from, to));
+        } else {
+            self.multiplicities.insert((from, to), count);
+        }
+    }
+
+    pub fn edge_multiplicity(&self, from: NodeId, to: NodeId) -> Option<u32> {
+        if !self.from(from)?.contains(&to) {
+            return None;
+        }
+
+        Some(self.multiplicities.get(&(from, to)).copied().unwrap_or(1))
+    }
+
+    // whether `connect_checked` would refuse the edge, a self-loop isn't a cycle here
+    pub fn would_create_cycle(&self, from: NodeId, to: NodeId) -> bool {
+        from != to && self.is_reachable(to, from)
+    }
+
+    pub fn connect_checked(&mut self, from: NodeId, to: NodeId) -> Result<(), Cycle> {
+        if let Some(path) = self.shortest_path(to, from) {
+            if let Some(cycle) = Cycle::new(path) {
+                return Err(cycle);
+            }
+        }
+
+        self.connect(from, to);
+
+        Ok(())
+    }
+
+    pub fn disconnect(&mut self, from: NodeId, to: NodeId) {
         // we don't want to create a node if it doesn't exist
         if let Some(connected) = self.nodes.get_mut(&from) {
             connected.remove(&to);
         }
+
+        self.weights.remove(&(from, to));
+        self.multiplicities.remove(&(from, to));
     }
 
-    pub fn subgraph(&self, nodes: &[u32]) -> Self {
+    pub fn subgraph(&self, nodes: &[NodeId]) -> Self {
         let mut subgraph = Graph::new(BTreeMap::new());
 
         for node in nodes {
@@ -56,6 +350,92 @@ impl Graph {
         subgraph
     }
 
+    // same as calling `subgraph` for every set, but walks the graph only once
+    pub fn subgraphs(&self, sets: &[Vec<NodeId>]) -> Vec<Self> {
+        let sets = sets
+            .iter()
+            .map(|set| set.iter().copied().collect::<BTreeSet<_>>())
+            .collect_vec();
+
+        let mut memberships = BTreeMap::<_, Vec<_>>::new();
+        for (i, set) in sets.iter().enumerate() {
+            for node in set.iter() {
+                memberships.entry(*node).or_default().push(i);
+            }
+        }
+
+        // only the nodes of some set are visited, and as nodes are only added along with an edge
+        // there's nothing for `cleanup` to remove
+        let mut subgraphs = vec![Graph::new(BTreeMap::new()); sets.len()];
+        for (from, memberships) in memberships.iter() {
+            let Some(connected) = self.nodes.get(from) else {
+                continue;
+            };
+
+            for to in connected.iter() {
+                for i in memberships.iter() {
+                    if sets[*i].contains(to) {
+                        subgraphs[*i].connect(*from, *to);
+                    }
+                }
+            }
+        }
+
+        subgraphs
+    }
+
+    // keeps the weights of the remaining edges, nodes left without edges are dropped
+    pub fn filter_edges(&self, keep: impl Fn(NodeId, NodeId) -> bool) -> Self {
+        let mut filtered = Graph::new(BTreeMap::new());
+
+        for (from, to) in self.edges().filter(|(from, to)| keep(*from, *to)) {
+            filtered.connect_weighted(from, to, self.weight(from, to).unwrap());
+        }
+
+        filtered.cleanup();
+
+        filtered
+    }
+
+    // every node stays, edges mapped onto each other are merged keeping the weight of the first
+    // one and the sum of their multiplicities, and the ones mapped to a self-loop are dropped
+    pub fn map_edges(&self, f: impl Fn(NodeId, NodeId) -> Option<(NodeId, NodeId)>) -> Self {
+        let mut mapped = Graph::with_nodes(self.nodes.keys().copied());
+
+        for (from, to) in self.edges() {
+            let Some((new_from, new_to)) = f(from, to) else {
+                continue;
+            };
+
+            let count = self.edge_multiplicity(from, to).unwrap();
+            match mapped.connect_reporting(new_from, new_to) {
+                ConnectOutcome::Added => {
+                    mapped.set_weight(new_from, new_to, self.weight(from, to).unwrap());
+                    mapped.set_multiplicity(new_from, new_to, count);
+                }
+                ConnectOutcome::AlreadyPresent => {
+                    let merged = mapped.edge_multiplicity(new_from, new_to).unwrap() + count;
+                    mapped.set_multiplicity(new_from, new_to, merged);
+                }
+                ConnectOutcome::SelfLoopRejected => {}
+            }
+        }
+
+        mapped
+    }
+
+    // in place counterpart of `filter_edges`, but every node stays, `cleanup` drops isolated ones
+    pub fn retain_edges(&mut self, keep: impl Fn(NodeId, NodeId) -> bool) {
+        let removed = self
+            .edges()
+            .filter(|(from, to)| !keep(*from, *to))
+            .collect_vec();
+
+        for (from, to) in removed {
+            self.disconnect(from, to);
+        }
+    }
+
     pub fn cleanup(&mut self) {
         let to_remove = self
             .nodes
@@ -71,7 +451,54 @@ impl Graph {
         }
     }
 
-    pub fn to(&self, to: u32) -> impl Iterator<Item = u32> + '_ {
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.nodes.values().map(|connected| connected.len()).sum()
+    }
+
+    // owned copy of the adjacency, in the same order as the sets
+    pub fn adjacency(&self) -> BTreeMap<NodeId, Vec<NodeId>> {
+        self.nodes
+            .iter()
+            .map(|(node, connected)| (*node, connected.iter().copied().collect()))
+            .collect()
+    }
+
+    // every edge in ascending order
+    pub fn edges(&self) -> impl Iterator<Item = (NodeId, NodeId)> + '_ {
+        self.nodes
+            .iter()
+            .flat_map(|(from, connected)| connected.iter().map(move |to| (*from, *to)))
+    }
+
+    // (added, removed) edges going from `self` to `other`
+    pub fn diff_edges(&self, other: &Graph) -> (EdgeList, EdgeList) {
+        let ours = self.edges().collect::<BTreeSet<_>>();
+        let theirs = other.edges().collect::<BTreeSet<_>>();
+
+        (
+            theirs.difference(&ours).cloned().collect(),
+            ours.difference(&theirs).cloned().collect(),
+        )
+    }
+
+    // one pass over the edges instead of calling `to` for every node
+    pub fn in_degrees(&self) -> BTreeMap<NodeId, usize> {
+        let mut in_degrees: BTreeMap<_, _> = self.nodes.keys().map(|id| (*id, 0)).collect();
+
+        for connected in self.nodes.values() {
+            for to in connected.iter() {
+                *in_degrees.get_mut(to).unwrap() += 1;
+            }
+        }
+
+        in_degrees
+    }
+
+    pub fn to(&self, to: NodeId) -> impl Iterator<Item = NodeId> + '_ {
         self.nodes.iter().filter_map(move |(from, connected)| {
             if connected.contains(&to) {
                 Some(*from)
@@ -81,20 +508,138 @@ impl Graph {
         })
     }
 
-    pub fn from(&self, id: u32) -> Option<&BTreeSet<u32>> {
+    pub fn from(&self, id: NodeId) -> Option<&BTreeSet<NodeId>> {
         self.nodes.get(&id)
     }
 
+    // same as `from`, the non-panicking counterpart of indexing
+    pub fn get(&self, id: NodeId) -> Option<&BTreeSet<NodeId>> {
+        self.from(id)
+    }
+
+    pub fn shortest_path(&self, from: NodeId, to: NodeId) -> Option<Vec<NodeId>> {
+        let mut previous = BTreeMap::from([(from, from)]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(node) = queue.pop_front() {
+            if node == to {
+                let mut path = vec![to];
+                while *path.last().unwrap() != from {
+                    path.push(previous[path.last().unwrap()]);
+                }
+                path.reverse();
+
+                return Some(path);
+            }
+
+            for connected in self.nodes.get(&node).into_iter().flatten() {
+                if !previous.contains_key(connected) {
+                    previous.insert(*connected, node);
+                    queue.push_back(*connected);
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn neighbors_undirected(&self, id: NodeId) -> BTreeSet<NodeId> {
+        let mut neighbors: BTreeSet<_> = self.to(id).collect();
+        neighbors.extend(self.from(id).into_iter().flatten());
+        neighbors.remove(&id);
+
+        neighbors
+    }
+
+    // stops as soon as `to` is found, a node in the graph always reaches itself
+    pub fn is_reachable(&self, from: NodeId, to: NodeId) -> bool {
+        self.nodes.contains_key(&from) && self.shortest_path(from, to).is_some()
+    }
+
+    pub fn reachable_from(&self, from: NodeId) -> BTreeSet<NodeId> {
+        self.multi_source_reachable(&[from])
+    }
+
+    // one search shared by all sources, every node is visited at most once, sources that aren't in
+    // the graph don't reach anything, not even themselves
+    pub fn multi_source_reachable(&self, sources: &[NodeId]) -> BTreeSet<NodeId> {
+        let mut reachable: BTreeSet<_> = sources
+            .iter()
+            .filter(|id| self.nodes.contains_key(id))
+            .copied()
+            .collect();
+        let mut queue: VecDeque<_> = reachable.iter().copied().collect();
+
+        while let Some(node) = queue.pop_front() {
+            for connected in self.nodes.get(&node).into_iter().flatten() {
+                if reachable.insert(*connected) {
+                    queue.push_back(*connected);
+                }
+            }
+        }
+
+        reachable
+    }
+
     pub fn find_cycle(&self) -> Option<Cycle> {
+        self.find_cycle_where(&|_| true)
+    }
+
+    // only follows edges between the given nodes, without building the subgraph
+    pub fn find_cycle_within(&self, nodes: &[NodeId]) -> Option<Cycle> {
+        let nodes: BTreeSet<_> = nodes.iter().copied().collect();
+        self.find_cycle_where(&|node| nodes.contains(&node))
+    }
+
+    // only walks up to `max_depth` edges away from each start, so longer cycles may be missed
+    pub fn find_cycle_bounded(&self, max_depth: usize) -> Option<Cycle> {
+        fn search_subtree(
+            graph: &Graph,
+            max_depth: usize,
+            ancestors: &mut Vec<NodeId>,
+        ) -> Option<Cycle> {
+            if ancestors.len() > max_depth {
+                return None;
+            }
+
+            for connected in graph.nodes.get(ancestors.last().unwrap()).unwrap() {
+                if let Some(cycle_start) = ancestors.iter().position(|x| x == connected) {
+                    return Cycle::new(ancestors[cycle_start..].to_vec());
+                }
+
+                ancestors.push(*connected);
+
+                if let Some(cycle) = search_subtree(graph, max_depth, ancestors) {
+                    return Some(cycle);
+                }
+
+                ancestors.pop();
+            }
+
+            None
+        }
+
+        // every node has to be a start, a bounded search from an earlier one doesn't cover it
+        self.nodes
+            .keys()
+            .find_map(|id| search_subtree(self, max_depth, &mut vec![*id]))
+    }
+
+    fn find_cycle_where(&self, keep: &dyn Fn(NodeId) -> bool) -> Option<Cycle> {
         let mut visited = BTreeSet::new();
 
         fn search_subtree(
             graph: &Graph,
-            ancestors: &mut Vec<u32>,
-            visited: &mut BTreeSet<u32>,
+            keep: &dyn Fn(NodeId) -> bool,
+            ancestors: &mut Vec<NodeId>,
+            visited: &mut BTreeSet<NodeId>,
         ) -> Option<Cycle> {
             if let Some(last) = ancestors.last() {
                 for connected in graph.nodes.get(last).unwrap() {
+                    if !keep(*connected) {
+                        continue;
+                    }
+
                     if let Some(cycle_start) = ancestors.iter().position(|x| x == connected) {
                         return Cycle::new(ancestors[cycle_start..].to_vec());
                     }
@@ -102,7 +647,7 @@ impl Graph {
                     ancestors.push(*connected);
                     visited.insert(*connected);
 
-                    if let Some(cycle) = search_subtree(graph, ancestors, visited) {
+                    if let Some(cycle) = search_subtree(graph, keep, ancestors, visited) {
                         return Some(cycle);
                     }
 
@@ -114,12 +659,12 @@ impl Graph {
         }
 
         for (id, _) in self.nodes.iter() {
-            if visited.contains(id) {
+            if visited.contains(id) || !keep(*id) {
                 continue;
             }
             let id = *id;
             visited.insert(id);
-            if let Some(cycle) = search_subtree(self, &mut vec![id], &mut visited) {
+            if let Some(cycle) = search_subtree(self, keep, &mut vec![id], &mut visited) {
                 return Some(cycle);
             }
         }
@@ -128,6 +673,11 @@ impl Graph {
     }
 
     pub fn check_cycle(&self, cycle: &Cycle) -> bool {
+        // `Cycle::new` doesn't allow these, but don't index into one blindly
+        if cycle.len() < 2 {
+            return false;
+        }
+
         cycle
             .slice()
             .iter()
@@ -141,7 +691,19 @@ impl Graph {
             })
     }
 
-    pub fn collapse_pair(&mut self, a: u32, b: u32) -> bool {
+    // an edge that ends up parallel to an existing one keeps the existing weight
+    pub fn collapse_pair(&mut self, a: NodeId, b: NodeId) -> bool {
+        self.collapse_pair_weighted(a, b, |kept, _| kept)
+    }
+
+    // `combine` gets the weight of the edge already on `a` and the one moved over from `b`, edges
+    // are moved in ascending order so equal graphs always collapse into equal graphs
+    pub fn collapse_pair_weighted(
+        &mut self,
+        a: NodeId,
+        b: NodeId,
+        combine: impl Fn(u64, u64) -> u64,
+    ) -> bool {
         if a == b || self.nodes.get(&a).is_none() || self.nodes.get(&b).is_none() {
             return false;
         }
@@ -155,129 +717,2948 @@ impl Graph {
         }
 
         for to in self.nodes.remove(&b).unwrap() {
+            let weight = self.weights.remove(&(b, to)).unwrap_or(1);
+            self.multiplicities.remove(&(b, to));
             if to == a {
                 continue;
             }
 
-            self.connect(a, to);
+            self.reconnect(a, to, weight, &combine);
         }
 
         for from in self.to(b).collect_vec() {
-            self.connect(from, a);
+            let weight = self.weight(from, b).unwrap();
             self.disconnect(from, b);
+            if from == a {
+                continue;
+            }
+
+            self.reconnect(from, a, weight, &combine);
         }
 
         true
     }
 
-    pub fn collapse_cycle(&mut self, cycle: &Cycle) -> bool {
-        if !self.check_cycle(cycle) {
+    // unlike `collapse_pair` the direction matters, `to` is merged into `from`
+    pub fn contract_edge(&mut self, from: NodeId, to: NodeId) -> bool {
+        if !self
+            .from(from)
+            .is_some_and(|connected| connected.contains(&to))
+        {
             return false;
         }
 
-        let (first, rest) = cycle.slice().split_first().unwrap();
+        self.collapse_pair(from, to)
+    }
 
-        for node in rest.iter() {
-            self.collapse_pair(*first, *node);
-        }
+    // moves an edge over from a collapsed node
+    fn reconnect(
+        &mut self,
+        from: NodeId,
+        to: NodeId,
+        weight: u64,
+        combine: &impl Fn(u64, u64) -> u64,
+    ) {
+        let weight = match self.weight(from, to) {
+            Some(existing) => combine(existing, weight),
+            None => weight,
+        };
 
-        true
+        self.connect(from, to);
+        self.set_weight(from, to, weight);
     }
 
-    pub fn simplify(&mut self) -> usize {
-        let mut removed = 0;
+    // the first problem found, going through `nodes` in the given order
+    pub fn validate_cycle(&self, nodes: &[NodeId]) -> Result<Cycle, CycleError> {
+        if nodes.len() < 2 {
+            return Err(CycleError::TooShort);
+        }
 
-        while let Some(cycle) = self.find_cycle() {
-            self.collapse_cycle(&cycle);
-            removed += cycle.len();
+        let mut seen = BTreeSet::new();
+        if let Some(duplicate) = nodes.iter().find(|node| !seen.insert(**node)) {
+            return Err(CycleError::DuplicateNode(*duplicate));
         }
 
-        self.cleanup();
+        if let Some((from, to)) = nodes.iter().circular_tuple_windows().find(|(from, to)| {
+            !self
+                .from(**from)
+                .is_some_and(|connected| connected.contains(to))
+        }) {
+            return Err(CycleError::MissingEdge(*from, *to));
+        }
 
-        removed
+        Ok(Cycle::normalized(nodes).unwrap())
     }
 
-    pub fn simplified(mut self) -> Self {
-        self.simplify();
-        self
+    // how many nodes `collapse_cycle` would remove, every node but the first merges into it
+    pub fn cycle_collapse_preview(&self, cycle: &Cycle) -> Option<usize> {
+        self.check_cycle(cycle).then(|| cycle.len() - 1)
     }
 
-    pub fn find_ends(&self) -> (Vec<u32>, Vec<u32>) {
-        let (mut starts, mut ends) = (Vec::new(), Vec::new());
-
-        for (id, connected) in self.nodes.iter() {
-            if connected.is_empty() {
-                ends.push(*id);
-            }
-
-            if self.to(*id).count() == 0 {
-                starts.push(*id);
-            }
+    pub fn collapse_cycle(&mut self, cycle: &Cycle) -> bool {
+        if !self.check_cycle(cycle) {
+            return false;
         }
 
-        (starts, ends)
+        let (first, rest) = cycle.slice().split_first().unwrap();
+        self.collapse_into(*first, rest);
+
+        true
     }
 
-    pub fn needed_to_connect(&self) -> Self {
-        let mut graph = self.clone().simplified();
+    // merges every node of `rest` into `first`, for a cycle that's the same as collapsing `first`
+    // with every other node in order, but the predecessors are only looked up once, edges already
+    // there or moved over earlier keep their weight
+    fn collapse_into(&mut self, first: NodeId, rest: &[NodeId]) {
+        let members: BTreeSet<_> = rest.iter().copied().collect();
+
+        let mut outgoing = Vec::new();
+        for node in rest.iter() {
+            for to in self.nodes.remove(node).unwrap() {
+                let weight = self.weights.remove(&(*node, to)).unwrap_or(1);
+                self.multiplicities.remove(&(*node, to));
+
+                if to != first && !members.contains(&to) {
+                    outgoing.push((to, weight));
+                }
+            }
+        }
+
+        let mut incoming = BTreeMap::<_, Vec<_>>::new();
+        for (from, connected) in self.nodes.iter() {
+            for to in connected.intersection(&members) {
+                incoming.entry(*to).or_default().push(*from);
+            }
+        }
+
+        for (to, weight) in outgoing {
+            self.reconnect(first, to, weight, &|kept, _| kept);
+        }
 
-        let (mut starts, mut to_connect) = graph.find_ends();
-        to_connect.append(&mut starts);
+        for node in rest.iter() {
+            for from in incoming.remove(node).into_iter().flatten() {
+                let weight = self.weight(from, *node).unwrap();
+                self.disconnect(from, *node);
 
-        for (from, to) in to_connect.iter().tuple_windows() {
-            graph.connect(*from, *to);
+                if from != first {
+                    self.reconnect(from, first, weight, &|kept, _| kept);
+                }
+            }
         }
+    }
 
-        graph.subgraph(&dbg!(to_connect))
+    pub fn simplify(&mut self) -> usize {
+        self.simplify_with_progress(|_| {})
     }
-}
 
-impl fmt::Debug for Graph {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let lines = self
-            .nodes
+    // `on_step` gets the running total that `simplify` returns after every collapse
+    pub fn simplify_with_progress(&mut self, mut on_step: impl FnMut(usize)) -> usize {
+        let mut removed = 0;
+
+        while let Some(cycle) = self.find_cycle() {
+            self.collapse_cycle(&cycle);
+            removed += cycle.len();
+            on_step(removed);
+        }
+
+        self.cleanup();
+
+        removed
+    }
+
+    // collapses at most `max_collapses` cycles, returns how many it did
+    pub fn simplify_n(&mut self, max_collapses: usize) -> usize {
+        let mut collapses = 0;
+
+        while collapses < max_collapses {
+            let Some(cycle) = self.find_cycle() else {
+                break;
+            };
+
+            self.collapse_cycle(&cycle);
+            collapses += 1;
+        }
+
+        self.cleanup();
+
+        collapses
+    }
+
+    // maps every absorbed node to the node it ended up in
+    pub fn simplify_with_mapping(&mut self) -> BTreeMap<NodeId, NodeId> {
+        let mut mapping = BTreeMap::new();
+
+        while let Some(cycle) = self.find_cycle() {
+            self.collapse_cycle(&cycle);
+
+            let (survivor, absorbed) = cycle.slice().split_first().unwrap();
+            for target in mapping.values_mut() {
+                if absorbed.contains(target) {
+                    *target = *survivor;
+                }
+            }
+            mapping.extend(absorbed.iter().map(|node| (*node, *survivor)));
+        }
+
+        self.cleanup();
+
+        mapping
+    }
+
+    // every strongly connected component becomes its smallest node, unlike `simplify_with_mapping`
+    // nodes left without edges are kept, maps every absorbed node to the node it ended up in
+    pub fn collapse_all_scc(&mut self) -> BTreeMap<NodeId, NodeId> {
+        let mut mapping = BTreeMap::new();
+
+        // a single node has nothing to collapse, and `collapse_into` still scans the whole graph
+        for component in self
+            .strongly_connected_components()
+            .into_iter()
+            .filter(|component| component.len() > 1)
+        {
+            let (survivor, absorbed) = component.split_first().unwrap();
+            self.collapse_into(*survivor, absorbed);
+            mapping.extend(absorbed.iter().map(|node| (*node, *survivor)));
+        }
+
+        mapping
+    }
+
+    pub fn simplified(mut self) -> Self {
+        self.simplify();
+        self
+    }
+
+    pub fn find_ends(&self) -> (Vec<NodeId>, Vec<NodeId>) {
+        let (mut starts, mut ends) = (Vec::new(), Vec::new());
+
+        for (id, connected) in self.nodes.iter() {
+            if connected.is_empty() {
+                ends.push(*id);
+            }
+
+            if self.to(*id).count() == 0 {
+                starts.push(*id);
+            }
+        }
+
+        (starts, ends)
+    }
+
+    // nodes without incoming edges, isolated nodes are both roots and leaves
+    pub fn roots(&self) -> Vec<NodeId> {
+        self.in_degrees()
+            .into_iter()
+            .filter(|(_, degree)| *degree == 0)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    // nodes without outgoing edges, isolated nodes are both roots and leaves
+    pub fn leaves(&self) -> Vec<NodeId> {
+        self.nodes
             .iter()
-            .map(|(n, c)| format!("{n} => {}", c.iter().map(|c| c.to_string()).join(", ")));
+            .filter(|(_, connected)| connected.is_empty())
+            .map(|(id, _)| *id)
+            .collect()
+    }
 
-        if f.alternate() {
-            write!(f, "Graph {{\n")?;
-            for line in lines {
-                write!(f, "    {};\n", line)?;
+    // the fewest edges that make the graph strongly connected, max(sources, sinks) of the
+    // condensation (Eswaran-Tarjan), the edges go between the representatives of the components
+    pub fn needed_to_connect(&self) -> Vec<(NodeId, NodeId)> {
+        let (condensation, _) = self.condensation();
+        if condensation.node_count() < 2 {
+            return Vec::new();
+        }
+
+        let (sources, sinks) = condensation.find_ends();
+        if sources.len() > sinks.len() {
+            return condensation
+                .transpose()
+                .needed_to_connect()
+                .into_iter()
+                .map(|(from, to)| (to, from))
+                .collect();
+        }
+
+        // pairs every source it can with a sink it reaches, such that the rest of the sources
+        // reach a paired sink and the rest of the sinks are reached from a paired source
+        fn find_sink(
+            graph: &Graph,
+            node: NodeId,
+            visited: &mut BTreeSet<NodeId>,
+        ) -> Option<NodeId> {
+            if !visited.insert(node) {
+                return None;
             }
+
+            if graph.nodes[&node].is_empty() {
+                return Some(node);
+            }
+
+            graph.nodes[&node]
+                .iter()
+                .find_map(|connected| find_sink(graph, *connected, visited))
+        }
+
+        let mut visited = BTreeSet::new();
+        let (mut paired_sources, mut paired_sinks) = (Vec::new(), Vec::new());
+        for source in sources.iter() {
+            if let Some(sink) = find_sink(&condensation, *source, &mut visited) {
+                paired_sources.push(*source);
+                paired_sinks.push(sink);
+            }
+        }
+
+        let paired = paired_sources.len();
+        let sources = paired_sources
+            .iter()
+            .chain(sources.iter().filter(|s| !paired_sources.contains(s)))
+            .copied()
+            .collect_vec();
+        let sinks = paired_sinks
+            .iter()
+            .chain(sinks.iter().filter(|s| !paired_sinks.contains(s)))
+            .copied()
+            .collect_vec();
+
+        let mut edges = Vec::new();
+        for i in 0..paired - 1 {
+            edges.push((sinks[i], sources[i + 1]));
+        }
+        for i in paired..sources.len() {
+            edges.push((sinks[i], sources[i]));
+        }
+
+        if sources.len() == sinks.len() {
+            edges.push((sinks[paired - 1], sources[0]));
         } else {
-            write!(f, "Graph {{ ")?;
-            for line in lines {
-                write!(f, "{}; ", line)?;
+            edges.push((sinks[paired - 1], sinks[sources.len()]));
+            for i in sources.len()..sinks.len() - 1 {
+                edges.push((sinks[i], sinks[i + 1]));
             }
+            edges.push((sinks[sinks.len() - 1], sources[0]));
         }
-        write!(f, "}}")?;
 
-        Ok(())
+        edges
     }
-}
 
-macro_rules! create_graph {
-        ($($($i:expr),+ => $($o:expr),+);+ $(;)?) => {{
-            use std::collections::{BTreeMap, BTreeSet};
+    fn dijkstra_tree(&self, from: NodeId) -> (BTreeMap<NodeId, u64>, BTreeMap<NodeId, NodeId>) {
+        let mut distances = BTreeMap::new();
+        let mut previous = BTreeMap::new();
+        let mut queue = BinaryHeap::<Reverse<(u64, NodeId)>>::new();
 
-            let mut nodes = BTreeMap::new();
+        if self.nodes.contains_key(&from) {
+            distances.insert(from, 0);
+            queue.push(Reverse((0, from)));
+        }
 
-            $(
-                let mut connected_to = BTreeSet::new();
-                $(
-                    connected_to.insert($o);
-                    nodes.entry($o).or_insert_with(|| BTreeSet::new());
-                )+
+        while let Some(Reverse((distance, node))) = queue.pop() {
+            if distances.get(&node).is_some_and(|d| *d < distance) {
+                continue;
+            }
 
-                $(
-                    nodes.entry($i).or_insert_with(|| BTreeSet::new()).extend(connected_to.clone());
-                 )+
-            )+
+            for connected in self.nodes.get(&node).unwrap() {
+                // a path longer than `u64::MAX` can't be told apart from the others, so it's skipped
+                let Some(through) = distance.checked_add(self.weight(node, *connected).unwrap())
+                else {
+                    continue;
+                };
 
-            $crate::graph::Graph::new(nodes)
-        }};
+                if distances.get(connected).is_none_or(|d| through < *d) {
+                    distances.insert(*connected, through);
+                    previous.insert(*connected, node);
+                    queue.push(Reverse((through, *connected)));
+                }
+            }
+        }
+
+        (distances, previous)
+    }
+
+    pub fn dijkstra(&self, from: NodeId) -> BTreeMap<NodeId, u64> {
+        self.dijkstra_tree(from).0
+    }
+
+    pub fn dijkstra_path(&self, from: NodeId, to: NodeId) -> Option<(u64, Vec<NodeId>)> {
+        let (distances, previous) = self.dijkstra_tree(from);
+        let distance = *distances.get(&to)?;
+
+        let mut path = vec![to];
+        while let Some(node) = previous.get(path.last().unwrap()) {
+            path.push(*node);
+        }
+        path.reverse();
+
+        Some((distance, path))
+    }
+
+    pub fn minimum_spanning_forest(&self) -> Self {
+        fn find(parents: &mut BTreeMap<NodeId, NodeId>, node: NodeId) -> NodeId {
+            let parent = parents[&node];
+            if parent == node {
+                return node;
+            }
+
+            let root = find(parents, parent);
+            parents.insert(node, root);
+            root
+        }
+
+        // undirected view, a pair connected both ways uses the lighter edge
+        let mut edges = BTreeMap::new();
+        for (from, connected) in self.nodes.iter() {
+            for to in connected.iter() {
+                let weight = self.weight(*from, *to).unwrap();
+                let edge = edges
+                    .entry((*from.min(to), *from.max(to)))
+                    .or_insert((weight, *from, *to));
+
+                if weight < edge.0 {
+                    *edge = (weight, *from, *to);
+                }
+            }
+        }
+
+        let mut forest = Graph::new(BTreeMap::new());
+        let mut parents = BTreeMap::new();
+        for id in self.nodes.keys() {
+            forest.with_node(*id);
+            parents.insert(*id, *id);
+        }
+
+        for (weight, from, to) in edges.into_values().sorted() {
+            let (from_root, to_root) = (find(&mut parents, from), find(&mut parents, to));
+            if from_root == to_root {
+                continue;
+            }
+
+            parents.insert(from_root, to_root);
+            forest.connect_weighted(from, to, weight);
+        }
+
+        forest
+    }
+
+    pub fn dominators(&self, root: NodeId) -> BTreeMap<NodeId, NodeId> {
+        fn postorder(
+            graph: &Graph,
+            node: NodeId,
+            visited: &mut BTreeSet<NodeId>,
+            order: &mut Vec<NodeId>,
+        ) {
+            visited.insert(node);
+
+            for connected in graph.nodes.get(&node).unwrap() {
+                if !visited.contains(connected) {
+                    postorder(graph, *connected, visited, order);
+                }
+            }
+
+            order.push(node);
+        }
+
+        let mut dominators = BTreeMap::new();
+        if !self.nodes.contains_key(&root) {
+            return dominators;
+        }
+
+        let mut order = Vec::new();
+        postorder(self, root, &mut BTreeSet::new(), &mut order);
+        order.reverse();
+
+        let index: BTreeMap<_, _> = order.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+        let intersect = |dominators: &BTreeMap<NodeId, NodeId>, mut a: NodeId, mut b: NodeId| {
+            while a != b {
+                while index[&a] > index[&b] {
+                    a = dominators[&a];
+                }
+                while index[&b] > index[&a] {
+                    b = dominators[&b];
+                }
+            }
+            a
+        };
+
+        dominators.insert(root, root);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for node in order[1..].iter() {
+                let dominator = self
+                    .to(*node)
+                    .filter(|pred| dominators.contains_key(pred))
+                    .reduce(|a, b| intersect(&dominators, a, b));
+
+                if let Some(dominator) = dominator {
+                    if dominators.insert(*node, dominator) != Some(dominator) {
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        dominators
+    }
+
+    pub fn topological_layers(&self) -> Result<Vec<Vec<NodeId>>, Cycle> {
+        if let Some(cycle) = self.find_untrusted_cycle() {
+            return Err(cycle);
+        }
+
+        Ok(self.acyclic_layers())
+    }
+
+    // `topological_layers` without looking for a cycle first, nodes on one are left out
+    pub(crate) fn acyclic_layers(&self) -> Vec<Vec<NodeId>> {
+        let mut in_degrees = self.in_degrees();
+
+        let mut layers = Vec::new();
+        let mut layer = in_degrees
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| *id)
+            .collect_vec();
+
+        while !layer.is_empty() {
+            let mut next = Vec::new();
+
+            for node in layer.iter() {
+                for connected in self.nodes.get(node).unwrap() {
+                    let degree = in_degrees.get_mut(connected).unwrap();
+                    *degree -= 1;
+
+                    if *degree == 0 {
+                        next.push(*connected);
+                    }
+                }
+            }
+
+            next.sort();
+            layers.push(layer);
+            layer = next;
+        }
+
+        layers
+    }
+
+    // treats every edge as going both ways
+    fn undirected_adjacency(&self) -> BTreeMap<NodeId, BTreeSet<NodeId>> {
+        let mut adjacency = self.nodes.clone();
+
+        for (from, connected) in self.nodes.iter() {
+            for to in connected.iter() {
+                adjacency.get_mut(to).unwrap().insert(*from);
+            }
+        }
+
+        adjacency
+    }
+
+    // visitation order of a breadth first search over `adjacency` from `start`
+    #[cfg(feature = "parallel")]
+    fn bfs(adjacency: &BTreeMap<NodeId, BTreeSet<NodeId>>, start: NodeId) -> Vec<NodeId> {
+        let mut order = vec![start];
+        let mut visited = BTreeSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            for connected in adjacency[&node].iter() {
+                if visited.insert(*connected) {
+                    order.push(*connected);
+                    queue.push_back(*connected);
+                }
+            }
+        }
+
+        order
+    }
+
+    // calls `visit` with the index of the component and the node, components in order of their
+    // smallest node
+    fn walk_weak_components(&self, mut visit: impl FnMut(usize, NodeId)) {
+        let adjacency = self.undirected_adjacency();
+        let mut visited = BTreeSet::new();
+        let mut component = 0;
+
+        for id in self.nodes.keys() {
+            if !visited.insert(*id) {
+                continue;
+            }
+
+            visit(component, *id);
+
+            let mut queue = VecDeque::from([*id]);
+            while let Some(node) = queue.pop_front() {
+                for connected in adjacency[&node].iter() {
+                    if visited.insert(*connected) {
+                        visit(component, *connected);
+                        queue.push_back(*connected);
+                    }
+                }
+            }
+
+            component += 1;
+        }
+    }
+
+    pub fn weakly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        let mut components: Vec<Vec<NodeId>> = Vec::new();
+
+        self.walk_weak_components(|component, node| match components.get_mut(component) {
+            Some(nodes) => nodes.push(node),
+            None => components.push(vec![node]),
+        });
+
+        for component in components.iter_mut() {
+            component.sort();
+        }
+
+        components
+    }
+
+    pub fn component_sizes(&self) -> Vec<usize> {
+        let mut sizes = Vec::new();
+
+        self.walk_weak_components(|component, _| match sizes.get_mut(component) {
+            Some(size) => *size += 1,
+            None => sizes.push(1),
+        });
+
+        sizes.sort_by(|a, b| b.cmp(a));
+        sizes
     }
 
-pub(crate) use create_graph;
+    // Tarjan's algorithm, components come out in reverse topological order
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        #[derive(Default)]
+        struct State {
+            indices: BTreeMap<NodeId, usize>,
+            lowlinks: BTreeMap<NodeId, usize>,
+            stack: Vec<NodeId>,
+            on_stack: BTreeSet<NodeId>,
+            components: Vec<Vec<NodeId>>,
+        }
+
+        fn visit(graph: &Graph, node: NodeId, state: &mut State) {
+            let index = state.indices.len();
+            state.indices.insert(node, index);
+            state.lowlinks.insert(node, index);
+            state.stack.push(node);
+            state.on_stack.insert(node);
+
+            for connected in graph.nodes.get(&node).unwrap() {
+                if !state.indices.contains_key(connected) {
+                    visit(graph, *connected, state);
+                    let lowlink = state.lowlinks[&node].min(state.lowlinks[connected]);
+                    state.lowlinks.insert(node, lowlink);
+                } else if state.on_stack.contains(connected) {
+                    let lowlink = state.lowlinks[&node].min(state.indices[connected]);
+                    state.lowlinks.insert(node, lowlink);
+                }
+            }
+
+            if state.lowlinks[&node] == index {
+                let mut component = Vec::new();
+                loop {
+                    let member = state.stack.pop().unwrap();
+                    state.on_stack.remove(&member);
+                    component.push(member);
+
+                    if member == node {
+                        break;
+                    }
+                }
 
-use crate::cycle::Cycle;
+                component.sort();
+                state.components.push(component);
+            }
+        }
+
+        let mut state = State::default();
+        for id in self.nodes.keys() {
+            if !state.indices.contains_key(id) {
+                visit(self, *id, &mut state);
+            }
+        }
+
+        state.components
+    }
+
+    // self-loops can't exist, so only components with more than one node are recursive
+    pub fn recursive_groups(&self) -> Vec<Vec<NodeId>> {
+        self.strongly_connected_components()
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .sorted()
+            .collect()
+    }
+
+    // every node reaches the first one and the first one reaches every node
+    pub fn is_strongly_connected(&self) -> bool {
+        let Some(first) = self.nodes.keys().next() else {
+            return true;
+        };
+
+        self.reachable_from(*first).len() == self.nodes.len()
+            && self.transpose().reachable_from(*first).len() == self.nodes.len()
+    }
+
+    pub fn is_weakly_connected(&self) -> bool {
+        self.weakly_connected_components().len() <= 1
+    }
+
+    // ties go to the component with the smallest node
+    pub fn largest_scc(&self) -> Vec<NodeId> {
+        self.strongly_connected_components()
+            .into_iter()
+            .min_by_key(|component| (Reverse(component.len()), component[0]))
+            .unwrap_or_default()
+    }
+
+    // over the nodes currently in the graph, all of them are kept even if they end up edgeless
+    pub fn complement(&self) -> Self {
+        let mut complement = Graph::new(BTreeMap::new());
+
+        for (from, connected) in self.nodes.iter() {
+            complement.with_node(*from).extend(
+                self.nodes
+                    .keys()
+                    .filter(|to| *to != from && !connected.contains(to)),
+            );
+        }
+
+        complement
+    }
+
+    // renumbers nodes to `0..node_count` keeping their order, returns the old to new mapping
+    pub fn compact_ids(&self) -> (Self, BTreeMap<NodeId, NodeId>) {
+        let mapping: BTreeMap<_, _> = self
+            .nodes
+            .keys()
+            .zip(0..)
+            .map(|(old, new)| (*old, new))
+            .collect();
+
+        let mut compacted = Graph::new(BTreeMap::new());
+        for (from, connected) in self.nodes.iter() {
+            compacted
+                .with_node(mapping[from])
+                .extend(connected.iter().map(|to| mapping[to]));
+        }
+
+        for ((from, to), weight) in self.weights.iter() {
+            compacted
+                .weights
+                .insert((mapping[from], mapping[to]), *weight);
+        }
+
+        for ((from, to), count) in self.multiplicities.iter() {
+            compacted
+                .multiplicities
+                .insert((mapping[from], mapping[to]), *count);
+        }
+
+        (compacted, mapping)
+    }
+
+    // checks that the graph is a forest, every node has at most one parent and there are no cycles
+    pub fn tree_roots(&self) -> Result<Vec<NodeId>, TreeError> {
+        let in_degrees = self.in_degrees();
+
+        if let Some((node, _)) = in_degrees.iter().find(|(_, degree)| **degree > 1) {
+            return Err(TreeError::MultipleParents(*node));
+        }
+
+        if let Some(cycle) = self.find_untrusted_cycle() {
+            return Err(TreeError::Cycle(cycle));
+        }
+
+        Ok(in_degrees
+            .into_iter()
+            .filter(|(_, degree)| *degree == 0)
+            .map(|(node, _)| node)
+            .collect())
+    }
+
+    // a single rooted tree: acyclic, every node but the one root has exactly one parent, so it's
+    // also connected with `node_count - 1` edges, an empty graph isn't one
+    pub fn is_tree(&self) -> bool {
+        match self.tree_roots() {
+            Ok(roots) => roots.len() == 1 && self.edge_count() + 1 == self.node_count(),
+            Err(_) => false,
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut transposed =
+            Graph::new(self.nodes.keys().map(|id| (*id, BTreeSet::new())).collect());
+
+        for (from, connected) in self.nodes.iter() {
+            for to in connected.iter() {
+                transposed.with_node(*to).insert(*from);
+            }
+        }
+
+        transposed.weights = self
+            .weights
+            .iter()
+            .map(|((from, to), weight)| ((*to, *from), *weight))
+            .collect();
+
+        transposed.multiplicities = self
+            .multiplicities
+            .iter()
+            .map(|((from, to), count)| ((*to, *from), *count))
+            .collect();
+
+        transposed
+    }
+
+    // every node that can reach `id`, not including `id` itself
+    pub fn ancestors(&self, id: NodeId) -> BTreeSet<NodeId> {
+        let mut ancestors = self.transpose().reachable_from(id);
+        ancestors.remove(&id);
+        ancestors
+    }
+
+    // roots that reach `id`, which is its own root if nothing points to it
+    pub fn source_roots_of(&self, id: NodeId) -> BTreeSet<NodeId> {
+        if !self.nodes.contains_key(&id) {
+            return BTreeSet::new();
+        }
+
+        let in_degrees = self.in_degrees();
+        self.transpose()
+            .reachable_from(id)
+            .into_iter()
+            .filter(|node| in_degrees[node] == 0)
+            .collect()
+    }
+
+    // common ancestors, counting the nodes themselves, which aren't an ancestor of another one
+    pub fn lowest_common_ancestors(&self, a: NodeId, b: NodeId) -> BTreeSet<NodeId> {
+        let transposed = self.transpose();
+
+        let common = &transposed.reachable_from(a) & &transposed.reachable_from(b);
+
+        let mut lowest = common.clone();
+        for node in common.iter() {
+            for ancestor in transposed.reachable_from(*node) {
+                if ancestor != *node {
+                    lowest.remove(&ancestor);
+                }
+            }
+        }
+
+        lowest
+    }
+
+    // two-colors the undirected view, `None` if an odd cycle makes that impossible
+    pub fn bipartition(&self) -> Option<(BTreeSet<NodeId>, BTreeSet<NodeId>)> {
+        let adjacency = self.undirected_adjacency();
+        let mut sides = BTreeMap::new();
+
+        for id in self.nodes.keys() {
+            if sides.contains_key(id) {
+                continue;
+            }
+
+            sides.insert(*id, false);
+            let mut queue = VecDeque::from([*id]);
+            while let Some(node) = queue.pop_front() {
+                let side = sides[&node];
+
+                for connected in adjacency[&node].iter() {
+                    match sides.get(connected) {
+                        Some(other) if *other == side => return None,
+                        Some(_) => {}
+                        None => {
+                            sides.insert(*connected, !side);
+                            queue.push_back(*connected);
+                        }
+                    }
+                }
+            }
+        }
+
+        let (left, right): (Vec<_>, Vec<_>) = sides.into_iter().partition(|(_, side)| !side);
+
+        Some((
+            left.into_iter().map(|(node, _)| node).collect(),
+            right.into_iter().map(|(node, _)| node).collect(),
+        ))
+    }
+
+    // augmenting paths over the undirected view, pairs are `(left, right)` of `bipartition`, empty
+    // if the graph isn't bipartite
+    pub fn maximum_bipartite_matching(&self) -> Vec<(NodeId, NodeId)> {
+        fn augment(
+            adjacency: &BTreeMap<NodeId, BTreeSet<NodeId>>,
+            node: NodeId,
+            visited: &mut BTreeSet<NodeId>,
+            matched: &mut BTreeMap<NodeId, NodeId>,
+        ) -> bool {
+            for connected in adjacency[&node].iter() {
+                if !visited.insert(*connected) {
+                    continue;
+                }
+
+                let free = match matched.get(connected) {
+                    Some(other) => augment(adjacency, *other, visited, matched),
+                    None => true,
+                };
+
+                if free {
+                    matched.insert(*connected, node);
+                    return true;
+                }
+            }
+
+            false
+        }
+
+        let Some((left, _)) = self.bipartition() else {
+            return Vec::new();
+        };
+
+        let adjacency = self.undirected_adjacency();
+        let mut matched = BTreeMap::new();
+        for node in left.iter() {
+            augment(&adjacency, *node, &mut BTreeSet::new(), &mut matched);
+        }
+
+        matched
+            .into_iter()
+            .map(|(right, left)| (left, right))
+            .sorted()
+            .collect()
+    }
+
+    // gcd of the lengths of all cycles, worked out per strongly connected component from bfs levels
+    pub fn cycle_period(&self) -> Option<u64> {
+        fn gcd(a: u64, b: u64) -> u64 {
+            if b == 0 {
+                a
+            } else {
+                gcd(b, a % b)
+            }
+        }
+
+        let mut period = 0;
+
+        for component in self.strongly_connected_components() {
+            if component.len() < 2 {
+                continue;
+            }
+
+            let members: BTreeSet<_> = component.iter().copied().collect();
+            let mut levels = BTreeMap::from([(component[0], 0u64)]);
+            let mut queue = VecDeque::from([component[0]]);
+
+            while let Some(node) = queue.pop_front() {
+                for connected in self.nodes[&node].iter() {
+                    if !members.contains(connected) {
+                        continue;
+                    }
+
+                    let level = levels[&node] + 1;
+                    match levels.get(connected) {
+                        Some(other) => period = gcd(period, level.abs_diff(*other)),
+                        None => {
+                            levels.insert(*connected, level);
+                            queue.push_back(*connected);
+                        }
+                    }
+                }
+            }
+        }
+
+        (period != 0).then_some(period)
+    }
+
+    // weights and multiplicities of edges present in both come from `other`
+    pub fn union(&self, other: &Graph) -> Self {
+        let mut union = self.clone();
+        union.merge_into(other.clone());
+        union
+    }
+
+    pub fn merge_into(&mut self, other: Graph) {
+        for (from, connected) in other.nodes.iter() {
+            self.with_node(*from).extend(connected.iter().copied());
+        }
+
+        // merging two dags can still close a cycle
+        self.trusted_dag = false;
+
+        // neither is stored when it's the default, so every edge of `other` has to be set
+        for (from, to) in other.edges() {
+            self.set_weight(from, to, other.weight(from, to).unwrap());
+            self.set_multiplicity(from, to, other.edge_multiplicity(from, to).unwrap());
+        }
+    }
+
+    // how many nodes have each out-degree
+    pub fn degree_histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for connected in self.nodes.values() {
+            *histogram.entry(connected.len()).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+
+    // sorted `(in_degree, out_degree)` pairs, equal for isomorphic graphs
+    pub fn degree_sequence(&self) -> Vec<(usize, usize)> {
+        self.in_degrees()
+            .into_iter()
+            .map(|(node, in_degree)| (in_degree, self.nodes[&node].len()))
+            .sorted()
+            .collect()
+    }
+
+    // removes the back edges of a depth first search, which leaves the graph acyclic, returns the
+    // removed edges
+    pub fn break_cycles(&mut self) -> Vec<(NodeId, NodeId)> {
+        // `false` while the node is on the stack, `true` once it's done
+        fn visit(
+            graph: &Graph,
+            node: NodeId,
+            states: &mut BTreeMap<NodeId, bool>,
+            back_edges: &mut Vec<(NodeId, NodeId)>,
+        ) {
+            states.insert(node, false);
+
+            for connected in graph.nodes[&node].iter() {
+                match states.get(connected) {
+                    Some(false) => back_edges.push((node, *connected)),
+                    Some(true) => {}
+                    None => visit(graph, *connected, states, back_edges),
+                }
+            }
+
+            states.insert(node, true);
+        }
+
+        let mut states = BTreeMap::new();
+        let mut back_edges = Vec::new();
+        for id in self.nodes.keys() {
+            if !states.contains_key(id) {
+                visit(self, *id, &mut states, &mut back_edges);
+            }
+        }
+
+        for (from, to) in back_edges.iter() {
+            self.disconnect(*from, *to);
+        }
+
+        back_edges
+    }
+
+    // every strongly connected component becomes its smallest node, also returns which node went
+    // where
+    pub fn condensation(&self) -> (Self, BTreeMap<NodeId, NodeId>) {
+        let mut representatives = BTreeMap::new();
+        for component in self.strongly_connected_components() {
+            for node in component.iter() {
+                representatives.insert(*node, component[0]);
+            }
+        }
+
+        let mut condensation = Graph::new(BTreeMap::new());
+        for (from, connected) in self.nodes.iter() {
+            condensation.with_node(representatives[from]);
+
+            for to in connected.iter() {
+                condensation.connect(representatives[from], representatives[to]);
+            }
+        }
+
+        (condensation, representatives)
+    }
+
+    // `Node 3 { from: 4, 5; to: 1, 2 }`, `from` are the out-neighbors and `to` the in-neighbors
+    pub fn debug_node(&self, id: NodeId) -> String {
+        match self.from(id) {
+            Some(from) => format!(
+                "Node {id} {{ from: {}; to: {} }}",
+                from.iter().join(", "),
+                self.to(id).join(", ")
+            ),
+            None => format!("Node {id} (absent)"),
+        }
+    }
+
+    // the compact `Debug` form, with at most `max_neighbors` listed per node before a `...`
+    pub fn debug_truncated(&self, max_neighbors: usize) -> String {
+        let lines = self.nodes.iter().map(|(n, c)| {
+            let mut neighbors = c
+                .iter()
+                .take(max_neighbors)
+                .map(|c| c.to_string())
+                .collect_vec();
+            if c.len() > max_neighbors {
+                neighbors.push("...".to_string());
+            }
+
+            format!("{n} => {}; ", neighbors.join(", "))
+        });
+
+        format!("Graph {{ {}}}", lines.collect::<String>())
+    }
+
+    // Brandes' algorithm over hop distances, runs a search from every node so it costs O(V * E)
+    pub fn edge_betweenness(&self) -> BTreeMap<(NodeId, NodeId), f64> {
+        let mut betweenness: BTreeMap<_, _> = self
+            .nodes
+            .iter()
+            .flat_map(|(from, connected)| connected.iter().map(|to| ((*from, *to), 0.0)))
+            .collect();
+
+        for source in self.nodes.keys() {
+            let mut order = Vec::new();
+            let mut predecessors = BTreeMap::<_, Vec<_>>::new();
+            let mut paths = BTreeMap::from([(*source, 1.0)]);
+            let mut distances = BTreeMap::from([(*source, 0)]);
+            let mut queue = VecDeque::from([*source]);
+
+            while let Some(node) = queue.pop_front() {
+                order.push(node);
+
+                for connected in self.nodes[&node].iter() {
+                    let distance = distances[&node] + 1;
+                    if !distances.contains_key(connected) {
+                        distances.insert(*connected, distance);
+                        queue.push_back(*connected);
+                    }
+
+                    if distances[connected] == distance {
+                        *paths.entry(*connected).or_insert(0.0) += paths[&node];
+                        predecessors.entry(*connected).or_default().push(node);
+                    }
+                }
+            }
+
+            let mut dependencies = BTreeMap::new();
+            for node in order.into_iter().rev() {
+                let dependency = dependencies.get(&node).copied().unwrap_or(0.0);
+
+                for predecessor in predecessors.get(&node).into_iter().flatten() {
+                    let share = paths[predecessor] / paths[&node] * (1.0 + dependency);
+                    *betweenness.get_mut(&(*predecessor, node)).unwrap() += share;
+                    *dependencies.entry(*predecessor).or_insert(0.0) += share;
+                }
+            }
+        }
+
+        betweenness
+    }
+
+    // hops from `from` to every node it reaches, itself included at 0
+    fn hop_distances(&self, from: NodeId) -> BTreeMap<NodeId, usize> {
+        let mut distances = BTreeMap::from([(from, 0)]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(node) = queue.pop_front() {
+            let distance = distances[&node];
+            for connected in self.nodes.get(&node).into_iter().flatten() {
+                if !distances.contains_key(connected) {
+                    distances.insert(*connected, distance + 1);
+                    queue.push_back(*connected);
+                }
+            }
+        }
+
+        distances
+    }
+
+    // rows and columns follow the returned node order, unreachable pairs are `None`
+    pub fn distance_matrix(&self) -> (Vec<NodeId>, Vec<Vec<Option<usize>>>) {
+        let order = self.nodes.keys().copied().collect_vec();
+
+        let matrix = order
+            .iter()
+            .map(|from| {
+                let distances = self.hop_distances(*from);
+                order.iter().map(|to| distances.get(to).copied()).collect()
+            })
+            .collect();
+
+        (order, matrix)
+    }
+
+    // `None` unless every node reaches every other one
+    fn eccentricities(&self) -> Option<Vec<usize>> {
+        let (_, matrix) = self.distance_matrix();
+
+        matrix
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .map(|row| row.into_iter().max().unwrap_or(0))
+            })
+            .collect()
+    }
+
+    // only defined for non-empty strongly connected graphs, otherwise some distance is infinite
+    pub fn diameter(&self) -> Option<usize> {
+        self.eccentricities()?.into_iter().max()
+    }
+
+    // only defined for non-empty strongly connected graphs, same as `diameter`
+    pub fn radius(&self) -> Option<usize> {
+        self.eccentricities()?.into_iter().min()
+    }
+
+    // the most hops from `id` to any node, `None` if some node can't be reached from it
+    pub fn eccentricity(&self, id: NodeId) -> Option<usize> {
+        if !self.nodes.contains_key(&id) {
+            return None;
+        }
+
+        let distances = self.hop_distances(id);
+        (distances.len() == self.nodes.len()).then(|| distances.into_values().max().unwrap())
+    }
+
+    // nodes with the smallest eccentricity, ones that don't reach everything are never picked
+    pub fn center(&self) -> Vec<NodeId> {
+        let eccentricities = self
+            .nodes
+            .keys()
+            .filter_map(|id| Some((*id, self.eccentricity(*id)?)))
+            .collect_vec();
+
+        let Some(min) = eccentricities.iter().map(|(_, e)| *e).min() else {
+            return Vec::new();
+        };
+
+        eccentricities
+            .into_iter()
+            .filter(|(_, e)| *e == min)
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    // every order there is, which can be up to `node_count!` of them, empty if there's a cycle
+    pub fn all_topological_sorts(&self) -> Vec<Vec<NodeId>> {
+        self.topological_sorts_limited(usize::MAX)
+    }
+
+    // stops after `limit` orders
+    pub fn topological_sorts_limited(&self, limit: usize) -> Vec<Vec<NodeId>> {
+        fn extend(
+            graph: &Graph,
+            in_degrees: &mut BTreeMap<NodeId, usize>,
+            order: &mut Vec<NodeId>,
+            sorts: &mut Vec<Vec<NodeId>>,
+            limit: usize,
+        ) {
+            if order.len() == in_degrees.len() {
+                sorts.push(order.clone());
+                return;
+            }
+
+            let available = in_degrees
+                .iter()
+                .filter(|(id, degree)| **degree == 0 && !order.contains(id))
+                .map(|(id, _)| *id)
+                .collect_vec();
+
+            for node in available {
+                if sorts.len() >= limit {
+                    return;
+                }
+
+                for connected in graph.nodes[&node].iter() {
+                    *in_degrees.get_mut(connected).unwrap() -= 1;
+                }
+                order.push(node);
+
+                extend(graph, in_degrees, order, sorts, limit);
+
+                order.pop();
+                for connected in graph.nodes[&node].iter() {
+                    *in_degrees.get_mut(connected).unwrap() += 1;
+                }
+            }
+        }
+
+        let mut sorts = Vec::new();
+        if self.find_untrusted_cycle().is_none() {
+            extend(
+                self,
+                &mut self.in_degrees(),
+                &mut Vec::new(),
+                &mut sorts,
+                limit,
+            );
+        }
+
+        sorts
+    }
+
+    // the nodes within `radius` hops of `center` ignoring edge directions, with every edge between
+    // them, empty if `center` isn't in the graph
+    pub fn ego_network(&self, center: NodeId, radius: usize) -> Self {
+        if !self.nodes.contains_key(&center) {
+            return Graph::new(BTreeMap::new());
+        }
+
+        let adjacency = self.undirected_adjacency();
+        let mut within = BTreeSet::from([center]);
+        let mut frontier = vec![center];
+
+        for _ in 0..radius {
+            frontier = frontier
+                .iter()
+                .flat_map(|node| adjacency[node].iter())
+                .filter(|node| within.insert(**node))
+                .copied()
+                .collect();
+        }
+
+        let mut ego = self.subgraph(&within.into_iter().collect_vec());
+        ego.add_node(center);
+
+        ego
+    }
+
+    // edges `a -> b` where `b` is also reachable from `a` through another successor, which a
+    // transitive reduction would drop, only defined for acyclic graphs
+    pub fn redundant_edges(&self) -> Result<EdgeList, Cycle> {
+        if let Some(cycle) = self.find_untrusted_cycle() {
+            return Err(cycle);
+        }
+
+        let mut redundant = Vec::new();
+        for (from, connected) in self.nodes.iter() {
+            for to in connected.iter() {
+                let others = connected
+                    .iter()
+                    .filter(|other| *other != to)
+                    .copied()
+                    .collect_vec();
+                if self.multi_source_reachable(&others).contains(to) {
+                    redundant.push((*from, *to));
+                }
+            }
+        }
+
+        Ok(redundant)
+    }
+
+    // adds the reverse of every edge that doesn't have one yet, with the same weight
+    pub fn to_undirected(&self) -> Self {
+        let mut undirected = self.clone();
+
+        for (from, to) in self.edges() {
+            if !self.nodes[&to].contains(&from) {
+                undirected.connect_weighted(to, from, self.weight(from, to).unwrap());
+            }
+        }
+
+        undirected
+    }
+
+    // `from` and everything it reaches with the edges between them, empty if `from` isn't there
+    pub fn subgraph_reachable(&self, from: NodeId) -> Self {
+        if !self.nodes.contains_key(&from) {
+            return Graph::new(BTreeMap::new());
+        }
+
+        let mut reachable = self.subgraph(&self.reachable_from(from).into_iter().collect_vec());
+        reachable.add_node(from);
+
+        reachable
+    }
+
+    // every simple path, there can be exponentially many of them
+    pub fn all_paths(&self, from: NodeId, to: NodeId) -> Vec<Vec<NodeId>> {
+        self.all_paths_bounded(from, to, usize::MAX)
+    }
+
+    // only paths with at most `max_depth` edges
+    pub fn all_paths_bounded(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        max_depth: usize,
+    ) -> Vec<Vec<NodeId>> {
+        fn extend(
+            graph: &Graph,
+            to: NodeId,
+            max_depth: usize,
+            path: &mut Vec<NodeId>,
+            on_path: &mut BTreeSet<NodeId>,
+            paths: &mut Vec<Vec<NodeId>>,
+        ) {
+            let node = *path.last().unwrap();
+            if node == to {
+                paths.push(path.clone());
+                return;
+            }
+
+            if path.len() > max_depth {
+                return;
+            }
+
+            for connected in graph.nodes[&node].iter() {
+                if on_path.insert(*connected) {
+                    path.push(*connected);
+                    extend(graph, to, max_depth, path, on_path, paths);
+                    path.pop();
+                    on_path.remove(connected);
+                }
+            }
+        }
+
+        let mut paths = Vec::new();
+        if self.nodes.contains_key(&from) && self.nodes.contains_key(&to) {
+            extend(
+                self,
+                to,
+                max_depth,
+                &mut vec![from],
+                &mut BTreeSet::from([from]),
+                &mut paths,
+            );
+        }
+
+        paths
+    }
+
+    // copy without the excluded nodes and their edges, every other node stays
+    pub fn without_nodes(&self, exclude: &BTreeSet<NodeId>) -> Self {
+        let keep = self
+            .nodes
+            .keys()
+            .filter(|id| !exclude.contains(id))
+            .copied()
+            .collect();
+
+        self.induced_subgraph(&keep)
+    }
+
+    // every node in `nodes` that's in the graph stays, even without edges, unlike `subgraph`
+    pub fn induced_subgraph(&self, nodes: &BTreeSet<NodeId>) -> Self {
+        let mut induced = Graph::new(BTreeMap::new());
+
+        for node in nodes.iter().filter(|node| self.nodes.contains_key(node)) {
+            induced.add_node(*node);
+
+            for to in self.nodes[node].intersection(nodes) {
+                induced.connect_weighted(*node, *to, self.weight(*node, *to).unwrap());
+            }
+        }
+
+        induced
+    }
+
+    // backtracking over candidates with the same degrees, exponential in the worst case
+    pub fn is_isomorphic(&self, other: &Graph) -> bool {
+        // `(in_degree, out_degree)` of every node
+        type Degrees = BTreeMap<NodeId, (usize, usize)>;
+
+        fn extend(
+            graph: &Graph,
+            other: &Graph,
+            order: &[NodeId],
+            degrees: &(Degrees, Degrees),
+            mapping: &mut BTreeMap<NodeId, NodeId>,
+            used: &mut BTreeSet<NodeId>,
+        ) -> bool {
+            let Some((node, rest)) = order.split_first() else {
+                return true;
+            };
+
+            for candidate in other.nodes.keys() {
+                if used.contains(candidate) || degrees.0[node] != degrees.1[candidate] {
+                    continue;
+                }
+
+                let consistent = mapping.iter().all(|(mapped, image)| {
+                    graph.nodes[node].contains(mapped) == other.nodes[candidate].contains(image)
+                        && graph.nodes[mapped].contains(node)
+                            == other.nodes[image].contains(candidate)
+                });
+                if !consistent {
+                    continue;
+                }
+
+                mapping.insert(*node, *candidate);
+                used.insert(*candidate);
+                if extend(graph, other, rest, degrees, mapping, used) {
+                    return true;
+                }
+                mapping.remove(node);
+                used.remove(candidate);
+            }
+
+            false
+        }
+
+        if self.node_count() != other.node_count()
+            || self.edge_count() != other.edge_count()
+            || self.degree_sequence() != other.degree_sequence()
+        {
+            return false;
+        }
+
+        let degrees = |graph: &Graph| {
+            let in_degrees = graph.in_degrees();
+            graph
+                .nodes
+                .iter()
+                .map(|(id, connected)| (*id, (in_degrees[id], connected.len())))
+                .collect::<Degrees>()
+        };
+
+        let order = self.nodes.keys().copied().collect_vec();
+        extend(
+            self,
+            other,
+            &order,
+            &(degrees(self), degrees(other)),
+            &mut BTreeMap::new(),
+            &mut BTreeSet::new(),
+        )
+    }
+
+    // both are simplified first, so which node each cycle collapsed into doesn't matter
+    pub fn structurally_equal_after_simplify(&self, other: &Graph) -> bool {
+        let (mut simplified, mut other) = (self.clone(), other.clone());
+        simplified.simplify();
+        other.simplify();
+
+        simplified.is_isomorphic(&other)
+    }
+
+    // Kirchhoff's theorem over the undirected view, the determinant of the laplacian without its
+    // first row and column, only meant for small graphs, 0 when disconnected or empty
+    pub fn spanning_tree_count(&self) -> u64 {
+        let adjacency = self.undirected_adjacency();
+        let order: BTreeMap<_, _> = adjacency.keys().zip(0..).map(|(id, i)| (*id, i)).collect();
+        if order.is_empty() {
+            return 0;
+        }
+
+        let size = order.len() - 1;
+        let mut laplacian = vec![vec![0i128; size]; size];
+        for (node, connected) in adjacency.iter().skip(1) {
+            let row = order[node] - 1;
+            laplacian[row][row] = connected.len() as i128;
+
+            for to in connected.iter().filter(|to| order[*to] > 0) {
+                laplacian[row][order[to] - 1] = -1;
+            }
+        }
+
+        // Bareiss elimination, every division is exact so this stays in integers
+        let mut previous = 1;
+        for k in 0..size {
+            if laplacian[k][k] == 0 {
+                let Some(swap) = (k + 1..size).find(|i| laplacian[*i][k] != 0) else {
+                    return 0;
+                };
+                // swapping rows flips the sign, but the determinant here is never negative
+                laplacian.swap(k, swap);
+            }
+
+            for i in k + 1..size {
+                for j in k + 1..size {
+                    laplacian[i][j] = (laplacian[i][j] * laplacian[k][k]
+                        - laplacian[i][k] * laplacian[k][j])
+                        / previous;
+                }
+            }
+            previous = laplacian[k][k];
+        }
+
+        previous.unsigned_abs() as u64
+    }
+
+    // rank flows along outgoing edges in proportion to their weights, which is plain pagerank as
+    // long as the weights are all 1, and nodes without any, or with only edges weighing 0, spread
+    // theirs over every node
+    pub fn pagerank(&self, damping: f64, iterations: usize) -> BTreeMap<NodeId, f64> {
+        let count = self.nodes.len() as f64;
+        let mut ranks: BTreeMap<_, _> = self.nodes.keys().map(|id| (*id, 1.0 / count)).collect();
+
+        let totals: BTreeMap<_, u64> = self
+            .nodes
+            .iter()
+            .map(|(from, connected)| {
+                let total = connected
+                    .iter()
+                    .map(|to| self.weight(*from, *to).unwrap())
+                    .sum();
+                (*from, total)
+            })
+            .collect();
+
+        for _ in 0..iterations {
+            let dangling: f64 = totals
+                .iter()
+                .filter(|(_, total)| **total == 0)
+                .map(|(id, _)| ranks[id])
+                .sum();
+
+            let base = (1.0 - damping) / count + damping * dangling / count;
+            let mut next: BTreeMap<_, _> = self.nodes.keys().map(|id| (*id, base)).collect();
+
+            for (from, connected) in self.nodes.iter() {
+                let total = totals[from];
+                if total == 0 {
+                    continue;
+                }
+
+                for to in connected.iter() {
+                    let share = self.weight(*from, *to).unwrap() as f64 / total as f64;
+                    *next.get_mut(to).unwrap() += damping * ranks[from] * share;
+                }
+            }
+
+            ranks = next;
+        }
+
+        ranks
+    }
+
+    // greedy, keeps removing the node of the first cycle found with the most edges left until no
+    // cycle is, so the set is small but not necessarily the smallest
+    pub fn feedback_vertex_set(&self) -> Vec<NodeId> {
+        let mut remaining = self.clone();
+        let mut removed = Vec::new();
+
+        while let Some(cycle) = remaining.find_cycle() {
+            let in_degrees = remaining.in_degrees();
+            let node = *cycle
+                .slice()
+                .iter()
+                .max_by_key(|id| (in_degrees[id] + remaining.nodes[id].len(), Reverse(**id)))
+                .unwrap();
+
+            removed.push(node);
+            remaining = remaining.without_nodes(&BTreeSet::from([node]));
+        }
+
+        removed
+    }
+}
+
+// panics if the node isn't in the graph, like indexing a `Vec` out of bounds
+impl Index<NodeId> for Graph {
+    type Output = BTreeSet<NodeId>;
+
+    fn index(&self, id: NodeId) -> &Self::Output {
+        match self.from(id) {
+            Some(connected) => connected,
+            None => panic!("node {id} is not in the graph"),
+        }
+    }
+}
+
+// self-loops are skipped, same as with `connect`
+impl FromIterator<(NodeId, NodeId)> for Graph {
+    fn from_iter<T: IntoIterator<Item = (NodeId, NodeId)>>(iter: T) -> Self {
+        let mut graph = Graph::new(BTreeMap::new());
+
+        for (from, to) in iter {
+            graph.connect(from, to);
+        }
+
+        graph
+    }
+}
+
+impl fmt::Debug for Graph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = self
+            .nodes
+            .iter()
+            .map(|(n, c)| format!("{n} => {}", c.iter().map(|c| c.to_string()).join(", ")));
+
+        if f.alternate() {
+            write!(f, "Graph {{\n")?;
+            for line in lines {
+                write!(f, "    {};\n", line)?;
+            }
+        } else {
+            write!(f, "Graph {{ ")?;
+            for line in lines {
+                write!(f, "{}; ", line)?;
+            }
+        }
+        write!(f, "}}")?;
+
+        Ok(())
+    }
+}
+
+#[macro_export]
+macro_rules! create_graph {
+        ($($($i:expr),+ => $($o:expr),+);+ $(;)?) => {{
+            use alloc::collections::{BTreeMap, BTreeSet};
+
+            let mut nodes = BTreeMap::new();
+
+            $(
+                let mut connected_to = BTreeSet::new();
+                $(
+                    connected_to.insert($o);
+                    nodes.entry($o).or_insert_with(|| BTreeSet::new());
+                )+
+
+                $(
+                    nodes.entry($i).or_insert_with(|| BTreeSet::new()).extend(connected_to.clone());
+                 )+
+            )+
+
+            $crate::graph::Graph::new(nodes)
+        }};
+    }
+
+pub use create_graph;
+
+use crate::cycle::{Cycle, CycleError};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cycle::Cycle;
+
+    #[test]
+    fn collapse_all_scc_keeps_isolated_representatives() {
+        let mut graph = create_graph! { 1 => 2; 2 => 1; 3 => 4; 4 => 5; 5 => 3 };
+
+        let mapping = graph.collapse_all_scc();
+
+        assert_eq!(graph, Graph::with_nodes([1, 3]));
+        assert_eq!(mapping, BTreeMap::from([(2, 1), (4, 3), (5, 3)]));
+    }
+
+    #[test]
+    fn collapse_all_scc_leaves_acyclic_parts() {
+        let mut graph = create_graph! { 1 => 2; 2 => 3; 3 => 2; 3 => 4 };
+
+        let mapping = graph.collapse_all_scc();
+
+        assert_eq!(graph, create_graph! { 1 => 2; 2 => 4 });
+        assert_eq!(mapping, BTreeMap::from([(3, 2)]));
+    }
+
+    #[test]
+    fn collapse_pair_is_deterministic() {
+        let edges = [(1, 2), (2, 1), (2, 3), (4, 2), (1, 5), (3, 1), (5, 4)];
+
+        let mut forward: Graph = edges.iter().copied().collect();
+        let mut backward: Graph = edges.iter().rev().copied().collect();
+        forward.connect_weighted(2, 6, 3);
+        backward.connect_weighted(2, 6, 3);
+        assert_eq!(forward, backward);
+
+        let mut again = forward.clone();
+        assert!(forward.collapse_pair(1, 2));
+        assert!(backward.collapse_pair(1, 2));
+        assert!(again.collapse_pair(1, 2));
+
+        assert_eq!(forward, backward);
+        assert_eq!(forward, again);
+        assert_eq!(format!("{forward:?}"), format!("{backward:?}"));
+        assert_eq!(forward.weight(1, 6), Some(3));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn serialization_ignores_insertion_order() {
+        let edges = [(3, 1), (1, 2), (2, 3), (5, 4), (1, 4)];
+
+        let mut forward = Graph::with_nodes([7]);
+        for (from, to) in edges.iter() {
+            forward.connect(*from, *to);
+        }
+
+        let mut backward = Graph::new(BTreeMap::new());
+        for (from, to) in edges.iter().rev() {
+            backward.connect(*from, *to);
+        }
+        backward.add_node(7);
+
+        assert_eq!(forward.to_dot(), backward.to_dot());
+        assert_eq!(forward.to_json(), backward.to_json());
+    }
+
+    #[test]
+    fn map_edges_reversed_is_transpose() {
+        let mut graph = create_graph! { 1 => 2, 3; 3 => 1; 4 => 1 };
+        graph.connect_weighted(2, 3, 4);
+        graph.connect_counted(1, 2);
+        graph.connect_counted(1, 2);
+        graph.add_node(5);
+
+        assert_eq!(graph.map_edges(|f, t| Some((t, f))), graph.transpose());
+    }
+
+    #[test]
+    fn map_edges_merges_multiplicities() {
+        let mut graph = create_graph! { 1 => 3; 2 => 3 };
+        graph.connect_counted(1, 3);
+        graph.connect_weighted(2, 4, 6);
+
+        let mapped = graph.map_edges(|f, t| Some((f.min(2), t.min(3))));
+
+        assert_eq!(mapped.edge_multiplicity(1, 3), Some(2));
+        assert_eq!(mapped.edge_multiplicity(2, 3), Some(2));
+        assert_eq!(mapped.weight(2, 3), Some(1));
+        assert_eq!(mapped.node_count(), 4);
+    }
+
+    #[test]
+    fn pagerank_with_a_dangling_node() {
+        let ranks = create_graph! { 1 => 2 }.pagerank(0.85, 100);
+
+        // r1 = 0.15 / 2 + 0.85 * r2 / 2 and r1 + r2 = 1
+        assert!((ranks[&1] - 0.5 / 1.425).abs() < 1e-9);
+        assert!((ranks[&2] - (1.0 - 0.5 / 1.425)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn pagerank_follows_weights() {
+        let mut graph = create_graph! { 2 => 1; 3 => 1 };
+        graph.connect_weighted(1, 2, 3);
+        graph.connect(1, 3);
+
+        let ranks = graph.pagerank(0.85, 100);
+
+        assert!((ranks.values().sum::<f64>() - 1.0).abs() < 1e-6);
+        // r2 = 0.05 + 0.85 * 0.75 * r1, r3 = 0.05 + 0.85 * 0.25 * r1, r1 = 0.05 + 0.85 * (r2 + r3)
+        let r1 = (0.05 + 0.85 * 0.1) / (1.0 - 0.85 * 0.85);
+        assert!((ranks[&1] - r1).abs() < 1e-6);
+        assert!((ranks[&2] - (0.05 + 0.85 * 0.75 * r1)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn pagerank_zero_weights_are_dangling() {
+        let mut graph = Graph::new(BTreeMap::new());
+        graph.connect_weighted(1, 2, 0);
+        graph.connect_weighted(2, 1, 0);
+
+        let ranks = graph.pagerank(0.85, 20);
+
+        assert_eq!(ranks, BTreeMap::from([(1, 0.5), (2, 0.5)]));
+    }
+
+    #[test]
+    fn is_reachable() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 4 => 3 };
+
+        assert!(graph.is_reachable(1, 3));
+        assert!(!graph.is_reachable(3, 1));
+        assert!(!graph.is_reachable(1, 4));
+        assert!(graph.is_reachable(2, 2));
+        assert!(!graph.is_reachable(99, 99));
+        assert!(!graph.is_reachable(1, 99));
+    }
+
+    #[test]
+    fn reachable_from_absent_node() {
+        let graph = create_graph! { 1 => 2 };
+
+        assert_eq!(graph.reachable_from(99), BTreeSet::new());
+        assert_eq!(graph.reachable_from(1), BTreeSet::from([1, 2]));
+        assert_eq!(graph.multi_source_reachable(&[99, 2]), BTreeSet::from([2]));
+    }
+
+    #[test]
+    fn merge_into_matches_union() {
+        let mut a = create_graph! { 1 => 2; 2 => 3 };
+        a.connect_weighted(3, 4, 7);
+        let mut b = create_graph! { 2 => 3; 4 => 5 };
+        b.connect_counted(4, 5);
+
+        let union = a.union(&b);
+        a.merge_into(b);
+
+        assert_eq!(a, union);
+        assert_eq!(a.edge_count(), 4);
+        assert_eq!(a.weight(3, 4), Some(7));
+        assert_eq!(a.edge_multiplicity(4, 5), Some(2));
+    }
+
+    #[test]
+    fn union_takes_default_weights_from_other() {
+        let mut a = Graph::with_nodes([1, 2]);
+        a.connect_weighted(1, 2, 5);
+        a.connect_counted(1, 2);
+        let b = create_graph! { 1 => 2 };
+
+        let union = a.union(&b);
+
+        assert_eq!(union.weight(1, 2), Some(1));
+        assert_eq!(union.edge_multiplicity(1, 2), Some(1));
+        assert_eq!(union, b);
+    }
+
+    fn weighted() -> Graph {
+        let mut graph = Graph::with_nodes([5]);
+        graph.connect_weighted(1, 2, 4);
+        graph.connect_weighted(1, 3, 1);
+        graph.connect_weighted(3, 2, 2);
+        graph.connect_weighted(2, 4, 1);
+        graph.connect_weighted(3, 4, 5);
+        graph
+    }
+
+    #[test]
+    fn dijkstra() {
+        let graph = weighted();
+
+        assert_eq!(
+            graph.dijkstra(1),
+            BTreeMap::from([(1, 0), (2, 3), (3, 1), (4, 4)])
+        );
+        assert_eq!(graph.dijkstra(3), BTreeMap::from([(2, 2), (3, 0), (4, 3)]));
+        assert_eq!(graph.dijkstra(9), BTreeMap::new());
+        assert_eq!(graph.dijkstra_path(1, 4), Some((4, vec![1, 3, 2, 4])));
+        assert_eq!(graph.dijkstra_path(1, 1), Some((0, vec![1])));
+        assert_eq!(graph.dijkstra_path(4, 1), None);
+        assert_eq!(graph.dijkstra_path(1, 5), None);
+    }
+
+    #[test]
+    fn minimum_spanning_forest() {
+        let mut graph = Graph::with_nodes([4, 5]);
+        graph.connect_weighted(1, 2, 1);
+        graph.connect_weighted(2, 3, 2);
+        graph.connect_weighted(3, 1, 3);
+        graph.connect_weighted(4, 5, 7);
+
+        let forest = graph.minimum_spanning_forest();
+
+        assert_eq!(forest.node_count(), graph.node_count());
+        assert_eq!(
+            forest.edge_count(),
+            graph.node_count() - graph.weakly_connected_components().len()
+        );
+        assert_eq!(forest.weight(3, 1), None);
+        assert_eq!(forest.weight(1, 2), Some(1));
+        assert_eq!(forest.weight(2, 3), Some(2));
+        assert_eq!(forest.weight(4, 5), Some(7));
+    }
+
+    #[test]
+    fn connect_checked() {
+        let mut graph = create_graph! { 1 => 2; 2 => 3 };
+
+        assert_eq!(graph.connect_checked(3, 4), Ok(()));
+        let before = graph.clone();
+
+        assert_eq!(
+            graph.connect_checked(4, 1),
+            Err(Cycle::new(vec![1, 2, 3, 4]).unwrap())
+        );
+        assert_eq!(graph, before);
+    }
+
+    #[test]
+    fn dominators() {
+        let graph = create_graph! { 1 => 2, 3; 2 => 4; 3 => 4; 4 => 5; 6 => 1 };
+
+        assert_eq!(
+            graph.dominators(1),
+            BTreeMap::from([(1, 1), (2, 1), (3, 1), (4, 1), (5, 4)])
+        );
+        assert_eq!(graph.dominators(9), BTreeMap::new());
+    }
+
+    #[test]
+    fn topological_layers() {
+        let graph = create_graph! { 1 => 2, 3; 2 => 4; 3 => 4 };
+
+        assert_eq!(
+            graph.topological_layers(),
+            Ok(vec![vec![1], vec![2, 3], vec![4]])
+        );
+
+        let cyclic = create_graph! { 1 => 2; 2 => 1 };
+        assert_eq!(
+            cyclic.topological_layers(),
+            Err(Cycle::new(vec![1, 2]).unwrap())
+        );
+    }
+
+    #[test]
+    fn contract_edge() {
+        let mut graph = create_graph! { 1 => 2 };
+        assert!(graph.contract_edge(1, 2));
+        assert_eq!(graph, Graph::with_nodes([1]));
+
+        let mut graph = create_graph! { 1 => 2; 2 => 3; 4 => 2 };
+        assert!(!graph.contract_edge(2, 1));
+        assert!(!graph.contract_edge(1, 3));
+        assert!(graph.contract_edge(1, 2));
+        assert_eq!(graph, create_graph! { 1 => 3; 4 => 1 });
+    }
+
+    #[test]
+    fn component_sizes() {
+        let mut graph = create_graph! { 1 => 2; 3 => 2 };
+        graph.add_node(4);
+
+        assert_eq!(graph.component_sizes(), vec![3, 1]);
+        assert_eq!(Graph::new(BTreeMap::new()).component_sizes(), vec![]);
+    }
+
+    #[test]
+    fn recursive_groups() {
+        // `main` calls `parse`, which calls `expr` and `term` that call each other, and `log`
+        let (main, parse, expr, term, log) = (1, 2, 3, 4, 5);
+        let graph = create_graph! {
+            main => parse, log;
+            parse => expr;
+            expr => term;
+            term => expr, log;
+        };
+
+        assert_eq!(graph.recursive_groups(), vec![vec![expr, term]]);
+        assert_eq!(
+            create_graph! { 1 => 2 }.recursive_groups(),
+            Vec::<Vec<_>>::new()
+        );
+    }
+
+    #[test]
+    fn simplify_with_mapping() {
+        let mut graph = create_graph! { 1 => 2; 2 => 3; 3 => 1; 3 => 4; 4 => 5; 5 => 4; 5 => 6 };
+
+        let mapping = graph.simplify_with_mapping();
+
+        assert_eq!(graph, create_graph! { 1 => 4; 4 => 6 });
+        assert_eq!(mapping, BTreeMap::from([(2, 1), (3, 1), (5, 4)]));
+        for absorbed in mapping.keys() {
+            assert!(graph.from(*absorbed).is_none());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "u64-ids")]
+    fn ids_past_u32() {
+        let large = u64::from(u32::MAX) + 1;
+        let mut graph = Graph::new(BTreeMap::new());
+        assert!(graph.connect(large, large + 5));
+
+        let debug = format!("{graph:?}");
+        assert_eq!(debug, "Graph { 4294967296 => 4294967301; 4294967301 => ; }");
+        assert_eq!(Graph::from_debug_str(&debug), Some(graph));
+    }
+
+    #[test]
+    fn check_degenerate_cycle() {
+        let graph = create_graph! { 1 => 2; 2 => 1 };
+
+        assert!(graph.check_cycle(&Cycle::new(vec![1, 2]).unwrap()));
+        assert!(!graph.check_cycle(&Cycle::unchecked(vec![])));
+        assert!(!graph.check_cycle(&Cycle::unchecked(vec![1])));
+        assert_eq!(
+            graph.cycle_collapse_preview(&Cycle::unchecked(vec![])),
+            None
+        );
+    }
+
+    // `count` nodes with `edges` random edges between them
+    fn random_graph(count: NodeId, edges: usize) -> Graph {
+        let mut graph = Graph::with_nodes(0..count);
+        for _ in 0..edges {
+            graph.connect(
+                rand::random::<NodeId>() % count,
+                rand::random::<NodeId>() % count,
+            );
+        }
+        graph
+    }
+
+    #[test]
+    fn subgraphs_match_subgraph() {
+        let graph = create_graph! { 1 => 2, 3; 2 => 3; 3 => 4; 4 => 1 };
+        let sets = [vec![1, 2, 3], vec![3, 4], vec![2, 4], vec![]];
+
+        let expected = sets.iter().map(|set| graph.subgraph(set)).collect_vec();
+        assert_eq!(graph.subgraphs(&sets), expected);
+        assert_eq!(expected[2], Graph::new(BTreeMap::new()));
+
+        let graph = random_graph(200, 1000);
+        let sets = (0..50)
+            .map(|i| (i * 4..i * 4 + 20).collect_vec())
+            .collect_vec();
+        let expected = sets.iter().map(|set| graph.subgraph(set)).collect_vec();
+        assert_eq!(graph.subgraphs(&sets), expected);
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with `--release -- --ignored --nocapture`"]
+    fn bench_subgraphs() {
+        let graph = random_graph(20_000, 100_000);
+        let sets = (0..100)
+            .map(|i| (i * 150..i * 150 + 2_000).collect_vec())
+            .collect_vec();
+
+        let start = std::time::Instant::now();
+        let expected = sets.iter().map(|set| graph.subgraph(set)).collect_vec();
+        std::println!("subgraph per set: {:?}", start.elapsed());
+
+        let start = std::time::Instant::now();
+        let subgraphs = graph.subgraphs(&sets);
+        std::println!("subgraphs: {:?}", start.elapsed());
+
+        assert_eq!(subgraphs, expected);
+    }
+
+    #[test]
+    fn complement() {
+        let mut graph = create_graph! { 1 => 2; 2 => 3 };
+        graph.add_node(4);
+
+        let complement = graph.complement();
+
+        assert_eq!(complement.node_count(), 4);
+        assert_eq!(complement.edge_count(), 4 * 3 - 2);
+        assert!(!complement.from(1).unwrap().contains(&2));
+        assert!(complement.from(2).unwrap().contains(&1));
+        assert_eq!(complement.complement(), graph);
+    }
+
+    #[test]
+    fn compact_ids() {
+        let mut graph = create_graph! { 10 => 20; 20 => 30; 30 => 10 };
+        graph.set_weight(20, 30, 4);
+
+        let (compacted, mapping) = graph.compact_ids();
+
+        assert_eq!(mapping, BTreeMap::from([(10, 0), (20, 1), (30, 2)]));
+        assert_eq!(
+            compacted.edges().collect_vec(),
+            vec![(0, 1), (1, 2), (2, 0)]
+        );
+        assert_eq!(compacted.weight(1, 2), Some(4));
+    }
+
+    #[test]
+    fn tree_roots() {
+        let mut forest = create_graph! { 1 => 2, 3; 3 => 4; 5 => 6 };
+        forest.add_node(7);
+        assert_eq!(forest.tree_roots(), Ok(vec![1, 5, 7]));
+
+        let two_parents = create_graph! { 1 => 3; 2 => 3 };
+        assert_eq!(two_parents.tree_roots(), Err(TreeError::MultipleParents(3)));
+
+        let cycle = create_graph! { 1 => 2; 2 => 1 };
+        assert_eq!(
+            cycle.tree_roots(),
+            Err(TreeError::Cycle(Cycle::new(vec![1, 2]).unwrap()))
+        );
+    }
+
+    #[test]
+    fn lowest_common_ancestors() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 3 => 4, 5; 6 => 2 };
+
+        assert_eq!(graph.lowest_common_ancestors(4, 5), BTreeSet::from([3]));
+        assert_eq!(graph.lowest_common_ancestors(3, 4), BTreeSet::from([3]));
+        assert_eq!(graph.lowest_common_ancestors(1, 6), BTreeSet::new());
+
+        let criss_cross = create_graph! { 1 => 3, 4; 2 => 3, 4 };
+        assert_eq!(
+            criss_cross.lowest_common_ancestors(3, 4),
+            BTreeSet::from([1, 2])
+        );
+    }
+
+    #[test]
+    fn neighbors_undirected() {
+        let graph = create_graph! { 1 => 2; 3 => 1; 2 => 1 };
+
+        assert_eq!(graph.neighbors_undirected(1), BTreeSet::from([2, 3]));
+        assert_eq!(graph.neighbors_undirected(2), BTreeSet::from([1]));
+        assert_eq!(graph.neighbors_undirected(9), BTreeSet::new());
+    }
+
+    #[test]
+    fn from_edge_list_counting() {
+        let (graph, ignored) =
+            Graph::from_edge_list_counting(&[(1, 2), (2, 3), (1, 2), (3, 3), (2, 3), (3, 1)]);
+
+        assert_eq!(graph, create_graph! { 1 => 2; 2 => 3; 3 => 1 });
+        assert_eq!(ignored, 3);
+    }
+
+    #[test]
+    fn maximum_bipartite_matching() {
+        // 1 and 2 both only fit 4, so one of them stays unmatched
+        let graph = create_graph! { 1 => 4; 2 => 4; 3 => 4, 5, 6 };
+
+        let matching = graph.maximum_bipartite_matching();
+
+        assert_eq!(matching.len(), 2);
+        let (left, right) = graph.bipartition().unwrap();
+        for (a, b) in matching.iter() {
+            assert!(left.contains(a) && right.contains(b));
+            assert!(graph.neighbors_undirected(*a).contains(b));
+        }
+
+        let triangle = create_graph! { 1 => 2; 2 => 3; 3 => 1 };
+        assert_eq!(triangle.maximum_bipartite_matching(), vec![]);
+    }
+
+    #[test]
+    fn cycle_period() {
+        let graph = create_graph! { 1 => 2; 2 => 1, 3; 3 => 4; 4 => 1 };
+        assert_eq!(graph.cycle_period(), Some(2));
+
+        let mixed = create_graph! { 1 => 2; 2 => 1, 3; 3 => 1 };
+        assert_eq!(mixed.cycle_period(), Some(1));
+
+        assert_eq!(
+            create_graph! { 1 => 2; 2 => 3; 3 => 1 }.cycle_period(),
+            Some(3)
+        );
+        assert_eq!(create_graph! { 1 => 2, 3; 2 => 3 }.cycle_period(), None);
+    }
+
+    #[test]
+    fn from_map() {
+        let dangling = BTreeMap::from([(1, BTreeSet::from([2])), (2, BTreeSet::from([3]))]);
+        assert_eq!(Graph::from_map(dangling.clone()), Err(3));
+        assert_eq!(Graph::from_map_unchecked(dangling.clone()).node_count(), 2);
+        assert_eq!(Graph::new(dangling).edge_count(), 2);
+
+        let self_loop = BTreeMap::from([(1, BTreeSet::from([1]))]);
+        assert_eq!(Graph::from_map(self_loop), Err(1));
+
+        let valid = BTreeMap::from([(1, BTreeSet::from([2])), (2, BTreeSet::new())]);
+        assert_eq!(Graph::from_map(valid), Ok(create_graph! { 1 => 2 }));
+    }
+
+    #[test]
+    fn find_cycle_within() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 3 => 1; 4 => 5; 5 => 4 };
+
+        assert_eq!(graph.find_cycle_within(&[1, 2, 4]), None);
+        assert_eq!(
+            graph.find_cycle_within(&[1, 2, 4, 5]),
+            Some(Cycle::new(vec![4, 5]).unwrap())
+        );
+        assert_eq!(graph.find_cycle_within(&[]), None);
+    }
+
+    #[test]
+    fn collapse_pair_weighted() {
+        let mut graph = create_graph! { 1 => 2; 2 => 1 };
+        graph.connect_weighted(3, 1, 2);
+        graph.connect_weighted(3, 2, 5);
+        graph.connect_weighted(2, 4, 3);
+
+        assert!(graph.collapse_pair_weighted(1, 2, |a, b| a + b));
+
+        assert_eq!(graph.weight(3, 1), Some(7));
+        assert_eq!(graph.weight(1, 4), Some(3));
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn degree_sequence() {
+        let graph = create_graph! { 1 => 2, 3; 2 => 3; 3 => 4 };
+        let relabeled = create_graph! { 40 => 30, 20; 30 => 20; 20 => 10 };
+
+        assert_eq!(graph.degree_sequence(), relabeled.degree_sequence());
+        assert_eq!(
+            graph.degree_sequence(),
+            vec![(0, 2), (1, 0), (1, 1), (2, 1)]
+        );
+    }
+
+    #[test]
+    fn cycles_iter() {
+        let graph = create_graph! { 1 => 2; 2 => 1, 3; 3 => 1, 4; 4 => 3 };
+
+        let iterated = graph.cycles_iter().collect::<BTreeSet<_>>();
+
+        assert_eq!(iterated, graph.all_cycles().into_iter().collect());
+        assert_eq!(
+            iterated,
+            BTreeSet::from([
+                Cycle::new(vec![1, 2]).unwrap(),
+                Cycle::new(vec![1, 2, 3]).unwrap(),
+                Cycle::new(vec![3, 4]).unwrap(),
+            ])
+        );
+        assert_eq!(
+            graph.cycles_iter().next(),
+            graph.all_cycles().first().cloned()
+        );
+    }
+
+    #[test]
+    fn find_cycle_bounded() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 3 => 4; 4 => 1; 5 => 6; 6 => 5 };
+
+        assert_eq!(graph.find_cycle_bounded(0), None);
+        assert_eq!(graph.find_cycle_bounded(1), None);
+        assert_eq!(
+            graph.find_cycle_bounded(3),
+            Some(Cycle::new(vec![5, 6]).unwrap())
+        );
+        assert_eq!(
+            graph.find_cycle_bounded(4),
+            Some(Cycle::new(vec![1, 2, 3, 4]).unwrap())
+        );
+    }
+
+    #[test]
+    fn with_nodes() {
+        let graph = Graph::with_nodes([3, 1, 2, 1]);
+
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 0);
+        assert_eq!(graph.adjacency().into_keys().collect_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn break_cycles() {
+        let mut graph = create_graph! { 1 => 2; 2 => 3, 4; 3 => 1; 4 => 2 };
+
+        let removed = graph.break_cycles();
+
+        assert_eq!(graph.find_cycle(), None);
+        assert_eq!(removed.len(), 2);
+        assert_eq!(graph.edge_count(), 3);
+        for (from, to) in removed {
+            assert!(graph.from(from).is_some_and(|c| !c.contains(&to)));
+        }
+    }
+
+    #[test]
+    fn needed_to_connect() {
+        let mut graph = create_graph! { 1 => 3, 4; 2 => 4, 5 };
+
+        let needed = graph.needed_to_connect();
+
+        assert_eq!(needed.len(), 3);
+        for (from, to) in needed {
+            graph.connect(from, to);
+        }
+        assert!(graph.is_strongly_connected());
+
+        let mut more_sources = create_graph! { 1 => 4; 2 => 4, 5; 3 => 5 };
+        let needed = more_sources.needed_to_connect();
+        assert_eq!(needed.len(), 3);
+        for (from, to) in needed {
+            more_sources.connect(from, to);
+        }
+        assert!(more_sources.is_strongly_connected());
+
+        assert_eq!(create_graph! { 1 => 2; 2 => 1 }.needed_to_connect(), vec![]);
+    }
+
+    #[test]
+    fn simplify_n() {
+        let mut graph = create_graph! { 1 => 2; 2 => 1; 3 => 4; 4 => 3; 2 => 3 };
+
+        assert_eq!(graph.simplify_n(1), 1);
+        assert!(graph.find_cycle().is_some());
+        assert_eq!(graph.simplify_n(5), 1);
+        assert_eq!(graph.find_cycle(), None);
+        assert_eq!(graph.simplify_n(0), 0);
+    }
+
+    #[test]
+    fn debug_node() {
+        let graph = create_graph! { 1 => 3; 2 => 3; 3 => 4, 5 };
+
+        assert_eq!(graph.debug_node(3), "Node 3 { from: 4, 5; to: 1, 2 }");
+        assert_eq!(graph.debug_node(4), "Node 4 { from: ; to: 3 }");
+        assert_eq!(graph.debug_node(9), "Node 9 (absent)");
+    }
+
+    #[test]
+    fn index() {
+        let graph = create_graph! { 1 => 2, 3 };
+
+        assert_eq!(graph[1], BTreeSet::from([2, 3]));
+        assert_eq!(graph[2], BTreeSet::new());
+        assert_eq!(graph.get(1), Some(&BTreeSet::from([2, 3])));
+        assert_eq!(graph.get(9), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "node 9 is not in the graph")]
+    fn index_absent() {
+        let _ = &create_graph! { 1 => 2 }[9];
+    }
+
+    #[test]
+    fn multi_source_reachable() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 4 => 3; 3 => 5; 6 => 7 };
+
+        let union = &graph.reachable_from(1) | &graph.reachable_from(4);
+        assert_eq!(
+            graph.multi_source_reachable(&[1, 4, 2]),
+            &union | &graph.reachable_from(2)
+        );
+        assert_eq!(union, BTreeSet::from([1, 2, 3, 4, 5]));
+        assert_eq!(graph.multi_source_reachable(&[]), BTreeSet::new());
+    }
+
+    #[test]
+    fn edge_betweenness() {
+        // 1 and 2 fan into the bridge 3 -> 4, which fans out to 5 and 6
+        let graph = create_graph! { 1, 2 => 3; 3 => 4; 4 => 5, 6 };
+
+        let betweenness = graph.edge_betweenness();
+
+        let (bridge, highest) = betweenness
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+        assert_eq!(*bridge, (3, 4));
+        // every path from one of 1, 2, 3 to one of 4, 5, 6 goes over it
+        assert_eq!(*highest, 9.0);
+        assert_eq!(betweenness[&(1, 3)], 4.0);
+        assert_eq!(betweenness.len(), graph.edge_count());
+    }
+
+    #[test]
+    fn diff_edges() {
+        let before = create_graph! { 1 => 2; 2 => 3 };
+        let after = create_graph! { 1 => 2; 3 => 1 };
+
+        assert_eq!(before.diff_edges(&after), (vec![(3, 1)], vec![(2, 3)]));
+        assert_eq!(before.diff_edges(&before), (vec![], vec![]));
+    }
+
+    #[test]
+    fn cycle_collapse_preview() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 3 => 1 };
+
+        assert_eq!(
+            graph.cycle_collapse_preview(&Cycle::new(vec![1, 2, 3]).unwrap()),
+            Some(2)
+        );
+        assert_eq!(
+            graph.cycle_collapse_preview(&Cycle::new(vec![1, 3, 2]).unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn debug_round_trip() {
+        let mut graph = create_graph! { 1 => 2, 3; 3 => 1 };
+        graph.add_node(7);
+
+        assert_eq!(
+            Graph::from_debug_str(&format!("{graph:?}")),
+            Some(graph.clone())
+        );
+        assert_eq!(Graph::from_debug_str(&format!("{graph:#?}")), Some(graph));
+        assert_eq!(Graph::from_debug_str("Graph { 1 => 2; }"), None);
+        assert_eq!(Graph::from_debug_str("Graph { 1 => ; 1 => ; }"), None);
+    }
+
+    #[test]
+    fn adjacency() {
+        let graph = create_graph! { 1 => 3, 2; 3 => 1 };
+
+        assert_eq!(
+            graph.adjacency(),
+            BTreeMap::from([(1, vec![2, 3]), (2, vec![]), (3, vec![1])])
+        );
+    }
+
+    #[test]
+    fn largest_scc() {
+        let graph = create_graph! { 1 => 2; 2 => 1, 3; 3 => 4; 4 => 5; 5 => 3, 6 };
+
+        assert_eq!(graph.largest_scc(), vec![3, 4, 5]);
+        assert_eq!(create_graph! { 2 => 1 }.largest_scc(), vec![1]);
+        assert_eq!(Graph::new(BTreeMap::new()).largest_scc(), vec![]);
+    }
+
+    #[test]
+    fn is_connected() {
+        let cycle = create_graph! { 1 => 2; 2 => 3; 3 => 1 };
+        assert!(cycle.is_strongly_connected());
+        assert!(cycle.is_weakly_connected());
+
+        let path = create_graph! { 1 => 2; 3 => 2 };
+        assert!(!path.is_strongly_connected());
+        assert!(path.is_weakly_connected());
+
+        let split = Graph::with_nodes([1, 2]);
+        assert!(!split.is_weakly_connected());
+
+        for trivial in [Graph::new(BTreeMap::new()), Graph::with_nodes([1])] {
+            assert!(trivial.is_strongly_connected());
+            assert!(trivial.is_weakly_connected());
+        }
+    }
+
+    #[test]
+    fn filter_edges() {
+        let mut graph = create_graph! { 1 => 2; 2 => 1; 3 => 1 };
+        graph.connect_weighted(1, 4, 3);
+
+        let filtered = graph.filter_edges(|from, to| to > from);
+
+        assert_eq!(filtered.edges().collect_vec(), vec![(1, 2), (1, 4)]);
+        assert_eq!(filtered.weight(1, 4), Some(3));
+        assert_eq!(filtered.from(3), None);
+    }
+
+    #[test]
+    fn debug_truncated() {
+        let mut graph = Graph::new(BTreeMap::new());
+        graph.connect_fanout(0, 1..=100);
+
+        let debug = graph.debug_truncated(3);
+
+        assert!(debug.starts_with("Graph { 0 => 1, 2, 3, ...; 1 => ; "));
+        assert_eq!(
+            create_graph! { 1 => 2 }.debug_truncated(1),
+            format!("{:?}", create_graph! { 1 => 2 })
+        );
+    }
+
+    #[test]
+    fn distance_matrix() {
+        let (order, matrix) = create_graph! { 1 => 2; 2 => 3 }.distance_matrix();
+
+        assert_eq!(order, vec![1, 2, 3]);
+        assert_eq!(
+            matrix,
+            vec![
+                vec![Some(0), Some(1), Some(2)],
+                vec![None, Some(0), Some(1)],
+                vec![None, None, Some(0)],
+            ]
+        );
+    }
+
+    #[test]
+    fn diameter_and_radius() {
+        let cycle = create_graph! { 1 => 2; 2 => 3; 3 => 4; 4 => 1 };
+        assert_eq!(cycle.diameter(), Some(3));
+        assert_eq!(cycle.radius(), Some(3));
+
+        let chain = create_graph! { 1 => 2; 2 => 3 };
+        assert_eq!(chain.diameter(), None);
+        assert_eq!(chain.radius(), None);
+        assert_eq!(Graph::new(BTreeMap::new()).diameter(), None);
+    }
+
+    #[test]
+    fn eccentricity_and_center() {
+        // a hub 1 going both ways with 2, 3 and 4
+        let graph = create_graph! { 1 => 2, 3, 4; 2, 3, 4 => 1 };
+
+        assert_eq!(graph.eccentricity(1), Some(1));
+        assert_eq!(graph.eccentricity(2), Some(2));
+        assert_eq!(graph.eccentricity(9), None);
+        assert_eq!(graph.center(), vec![1]);
+
+        let chain = create_graph! { 1 => 2; 2 => 3 };
+        assert_eq!(chain.eccentricity(2), None);
+        assert_eq!(chain.center(), vec![1]);
+    }
+
+    #[test]
+    fn connect_fanout() {
+        let mut graph = create_graph! { 1 => 2 };
+
+        assert_eq!(graph.connect_fanout(1, [2, 3, 1, 4]), 2);
+        assert_eq!(graph, create_graph! { 1 => 2, 3, 4 });
+    }
+
+    #[test]
+    fn connect_fanin() {
+        let mut graph = create_graph! { 1 => 4 };
+
+        assert_eq!(graph.connect_fanin([1, 2, 3, 4], 4), 2);
+        assert_eq!(graph, create_graph! { 1, 2, 3 => 4 });
+        assert_eq!(graph.connect_fanin([5, 6, 7], 4), 3);
+        assert_eq!(graph.in_degrees()[&4], 6);
+    }
+
+    #[test]
+    fn connect_reporting() {
+        let mut graph = Graph::new(BTreeMap::new());
+
+        assert_eq!(graph.connect_reporting(1, 2), ConnectOutcome::Added);
+        assert_eq!(
+            graph.connect_reporting(1, 2),
+            ConnectOutcome::AlreadyPresent
+        );
+        assert_eq!(
+            graph.connect_reporting(3, 3),
+            ConnectOutcome::SelfLoopRejected
+        );
+        assert_eq!(graph, create_graph! { 1 => 2 });
+    }
+
+    #[test]
+    fn is_tree() {
+        let mut graph = create_graph! { 1 => 2, 3; 2 => 4, 5 };
+        assert!(graph.is_tree());
+
+        graph.connect(3, 5);
+        assert!(!graph.is_tree());
+
+        assert!(!create_graph! { 1 => 2; 3 => 4 }.is_tree());
+        assert!(!create_graph! { 1 => 2; 2 => 1 }.is_tree());
+        assert!(!Graph::new(BTreeMap::new()).is_tree());
+    }
+
+    #[test]
+    fn all_topological_sorts() {
+        let graph = create_graph! { 1 => 2, 3; 2, 3 => 4 };
+
+        assert_eq!(
+            graph.all_topological_sorts(),
+            vec![vec![1, 2, 3, 4], vec![1, 3, 2, 4]]
+        );
+        assert_eq!(graph.topological_sorts_limited(1), vec![vec![1, 2, 3, 4]]);
+        assert!(create_graph! { 1 => 2; 2 => 1 }
+            .all_topological_sorts()
+            .is_empty());
+    }
+
+    #[test]
+    fn ego_network() {
+        let graph = create_graph! { 1 => 2; 3 => 2; 2 => 4; 4 => 5 };
+
+        assert_eq!(
+            graph.ego_network(2, 1),
+            create_graph! { 1 => 2; 3 => 2; 2 => 4 }
+        );
+        assert_eq!(graph.ego_network(2, 0), Graph::with_nodes([2]));
+        assert_eq!(graph.ego_network(9, 1), Graph::new(BTreeMap::new()));
+    }
+
+    #[test]
+    fn connect_counted() {
+        let mut graph = Graph::new(BTreeMap::new());
+
+        assert!(graph.connect_counted(1, 2));
+        assert!(graph.connect_counted(1, 2));
+        assert!(!graph.connect_counted(3, 3));
+
+        assert_eq!(graph.edge_multiplicity(1, 2), Some(2));
+        assert_eq!(graph.edge_multiplicity(2, 1), None);
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.from(1), Some(&BTreeSet::from([2])));
+    }
+
+    #[test]
+    fn redundant_edges() {
+        assert_eq!(
+            create_graph! { 1 => 2, 3; 2 => 3 }.redundant_edges(),
+            Ok(vec![(1, 3)])
+        );
+        assert_eq!(
+            create_graph! { 1 => 2; 2 => 3 }.redundant_edges(),
+            Ok(vec![])
+        );
+        assert!(create_graph! { 1 => 2; 2 => 1 }.redundant_edges().is_err());
+    }
+
+    #[test]
+    fn to_undirected() {
+        let graph = create_graph! { 1 => 2, 3; 3 => 1; 2 => 4 };
+        let undirected = graph.to_undirected();
+
+        for (from, to) in undirected.edges() {
+            assert!(undirected.from(to).unwrap().contains(&from));
+        }
+        assert_eq!(undirected.edge_count(), 6);
+        assert_eq!(undirected.node_count(), graph.node_count());
+    }
+
+    #[test]
+    fn simplify_with_progress() {
+        let graph = create_graph! { 1 => 2; 2 => 1; 3 => 4; 4 => 5; 5 => 3; 2 => 3 };
+        let mut steps = Vec::new();
+
+        let mut simplified = graph.clone();
+        let removed = simplified.simplify_with_progress(|removed| steps.push(removed));
+
+        assert_eq!(steps.len(), 2);
+        assert!(steps.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(steps.last(), Some(&removed));
+        assert_eq!(simplified, graph.clone().simplified());
+        assert_eq!(removed, graph.clone().simplify());
+    }
+
+    #[test]
+    fn subgraph_reachable() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 4 => 5 };
+
+        assert_eq!(
+            graph.subgraph_reachable(1),
+            create_graph! { 1 => 2; 2 => 3 }
+        );
+        assert_eq!(graph.subgraph_reachable(3), Graph::with_nodes([3]));
+        assert_eq!(graph.subgraph_reachable(9), Graph::new(BTreeMap::new()));
+    }
+
+    #[test]
+    fn all_paths() {
+        let diamond = create_graph! { 1 => 2, 3; 2, 3 => 4 };
+        assert_eq!(diamond.all_paths(1, 4), vec![vec![1, 2, 4], vec![1, 3, 4]]);
+        assert!(diamond.all_paths(4, 1).is_empty());
+
+        // the cycle back to 1 doesn't make it loop forever
+        let cyclic = create_graph! { 1 => 2, 3; 2, 3 => 4; 4 => 1 };
+        assert_eq!(cyclic.all_paths(1, 4).len(), 2);
+        assert_eq!(
+            create_graph! { 1 => 2, 4; 2 => 3; 3 => 4 }.all_paths_bounded(1, 4, 2),
+            vec![vec![1, 4]]
+        );
+    }
+
+    #[test]
+    fn induced_subgraph() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 3 => 4 };
+
+        assert_eq!(
+            graph.induced_subgraph(&BTreeSet::from([1, 3])),
+            Graph::with_nodes([1, 3])
+        );
+        assert_eq!(
+            graph.induced_subgraph(&BTreeSet::from([2, 3, 9])),
+            create_graph! { 2 => 3 }
+        );
+    }
+
+    #[test]
+    fn structurally_equal_after_simplify() {
+        // both collapse to a chain of two, into different representatives
+        let first = create_graph! { 1 => 2; 2 => 3; 3 => 1; 3 => 4 };
+        let second = create_graph! { 7 => 5; 5 => 6; 6 => 5 };
+
+        assert!(first.structurally_equal_after_simplify(&second));
+        assert!(!first.structurally_equal_after_simplify(&create_graph! { 1 => 2; 3 => 4 }));
+    }
+
+    #[test]
+    fn roots_and_leaves() {
+        let mut tree = create_graph! { 1 => 2, 3; 2 => 4, 5 };
+        assert_eq!(tree.roots(), vec![1]);
+        assert_eq!(tree.leaves(), vec![3, 4, 5]);
+
+        // isolated nodes are both
+        tree.add_node(6);
+        assert_eq!(tree.roots(), vec![1, 6]);
+        assert_eq!(tree.leaves(), vec![3, 4, 5, 6]);
+        assert_eq!(tree.find_ends(), (tree.roots(), tree.leaves()));
+    }
+
+    #[test]
+    fn would_create_cycle() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 4 => 3 };
+
+        assert!(graph.would_create_cycle(3, 1));
+        assert!(graph.would_create_cycle(2, 1));
+        assert!(!graph.would_create_cycle(1, 3));
+        assert!(!graph.would_create_cycle(4, 1));
+        assert!(!graph.would_create_cycle(1, 1));
+        assert!(!graph.would_create_cycle(9, 1));
+    }
+
+    #[test]
+    fn spanning_tree_count() {
+        assert_eq!(
+            create_graph! { 1 => 2; 2 => 3; 3 => 1 }.spanning_tree_count(),
+            3
+        );
+        // directions are ignored, a 4-cycle with a chord has 8
+        assert_eq!(
+            create_graph! { 1 => 2; 2 => 3; 3 => 4; 4 => 1; 1 => 3 }.spanning_tree_count(),
+            8
+        );
+        assert_eq!(create_graph! { 1 => 2; 3 => 4 }.spanning_tree_count(), 0);
+        assert_eq!(Graph::new(BTreeMap::new()).spanning_tree_count(), 0);
+    }
+
+    #[test]
+    fn retain_edges() {
+        let mut graph = create_graph! { 1 => 2, 3; 2 => 4; 3 => 5 };
+        graph.retain_edges(|_, to| to % 2 == 0);
+
+        let mut expected = create_graph! { 1 => 2; 2 => 4 };
+        expected.add_node(3);
+        expected.add_node(5);
+        assert_eq!(graph, expected);
+    }
+
+    #[test]
+    fn source_roots_of() {
+        let graph = create_graph! { 1 => 3; 2 => 4; 3 => 4, 5 };
+
+        assert_eq!(graph.source_roots_of(5), BTreeSet::from([1]));
+        assert_eq!(graph.source_roots_of(4), BTreeSet::from([1, 2]));
+        assert_eq!(graph.source_roots_of(1), BTreeSet::from([1]));
+        assert!(graph.source_roots_of(9).is_empty());
+    }
+
+    #[test]
+    fn feedback_vertex_set() {
+        let graph = create_graph! { 1 => 2, 4; 2 => 3; 3 => 1; 4 => 5; 5 => 1 };
+        let removed = graph.feedback_vertex_set();
+        assert_eq!(removed, vec![1]);
+
+        assert!(graph
+            .without_nodes(&removed.into_iter().collect())
+            .find_cycle()
+            .is_none());
+
+        assert!(create_graph! { 1 => 2 }.feedback_vertex_set().is_empty());
+    }
+
+    #[test]
+    fn without_nodes() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 3 => 4; 4 => 5 };
+        let split = graph.without_nodes(&BTreeSet::from([3]));
+
+        assert_eq!(split, create_graph! { 1 => 2; 4 => 5 });
+        assert_eq!(
+            split.weakly_connected_components(),
+            vec![vec![1, 2], vec![4, 5]]
+        );
+        assert_eq!(graph.without_nodes(&BTreeSet::new()), graph);
+    }
+
+    #[test]
+    fn validate_cycle() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 3 => 1; 3 => 4 };
+
+        assert_eq!(
+            graph.validate_cycle(&[2, 3, 1]),
+            Ok(Cycle::new(vec![1, 2, 3]).unwrap())
+        );
+        assert_eq!(
+            graph.validate_cycle(&[1, 2, 3, 4]),
+            Err(CycleError::MissingEdge(4, 1))
+        );
+        assert_eq!(
+            graph.validate_cycle(&[1, 2, 1]),
+            Err(CycleError::DuplicateNode(1))
+        );
+        assert_eq!(graph.validate_cycle(&[1]), Err(CycleError::TooShort));
+    }
+
+    #[test]
+    fn trusted_dag() {
+        let mut graph = Graph::new_trusted_dag(create_graph! { 1 => 2; 2 => 3 }.nodes);
+        assert!(graph.is_trusted_dag());
+
+        graph.connect(1, 3);
+        assert!(graph.is_trusted_dag());
+
+        graph.connect(3, 1);
+        assert!(!graph.is_trusted_dag());
+        assert!(graph.find_untrusted_cycle().is_some());
+        assert!(!Graph::new(BTreeMap::new()).is_trusted_dag());
+    }
+
+    // what `collapse_cycle` did before it looked the predecessors up only once
+    fn collapse_pairwise(graph: &mut Graph, cycle: &Cycle) {
+        let (first, rest) = cycle.slice().split_first().unwrap();
+        for node in rest.iter() {
+            graph.collapse_pair(*first, *node);
+        }
+    }
+
+    #[test]
+    fn collapse_cycle_matches_pairwise() {
+        let mut graph = create_graph! { 1 => 2; 2 => 3; 3 => 1; 4 => 2; 3 => 5; 1 => 5 };
+        graph.connect_weighted(4, 3, 7);
+        let cycle = Cycle::new(vec![1, 2, 3]).unwrap();
+
+        let mut pairwise = graph.clone();
+        collapse_pairwise(&mut pairwise, &cycle);
+        assert!(graph.collapse_cycle(&cycle));
+        assert_eq!(graph, pairwise);
+        assert_eq!(graph, create_graph! { 4 => 1; 1 => 5 });
+        assert_eq!(graph.weight(4, 1), Some(1));
+
+        for _ in 0..200 {
+            let mut graph = random_graph(30, 60);
+            for (from, to) in graph.edges().step_by(3).collect_vec() {
+                graph.set_weight(from, to, rand::random::<u64>() % 10 + 2);
+            }
+
+            let Some(cycle) = graph.find_cycle() else {
+                continue;
+            };
+            let mut pairwise = graph.clone();
+            collapse_pairwise(&mut pairwise, &cycle);
+            graph.collapse_cycle(&cycle);
+            assert_eq!(graph, pairwise);
+        }
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with `--release -- --ignored --nocapture`"]
+    fn bench_collapse_cycle() {
+        let mut graph = Graph::with_nodes(0..3_000);
+        for node in 0..3_000 {
+            graph.connect(node, (node + 1) % 3_000);
+            graph.connect(node, rand::random::<NodeId>() % 3_000);
+        }
+        let cycle = Cycle::new((0..3_000).collect()).unwrap();
+
+        let mut pairwise = graph.clone();
+        let start = std::time::Instant::now();
+        collapse_pairwise(&mut pairwise, &cycle);
+        std::println!("pairwise: {:?}", start.elapsed());
+
+        let start = std::time::Instant::now();
+        graph.collapse_cycle(&cycle);
+        std::println!("single pass: {:?}", start.elapsed());
+
+        assert_eq!(graph, pairwise);
+    }
+
+    #[test]
+    fn degree_histogram() {
+        let graph = create_graph! { 1 => 2, 3, 4, 5; 2 => 3; 3 => 4 };
+
+        assert_eq!(
+            graph.degree_histogram(),
+            BTreeMap::from([(0, 2), (1, 2), (4, 1)])
+        );
+        assert!(Graph::new(BTreeMap::new()).degree_histogram().is_empty());
+    }
+
+    #[test]
+    fn dijkstra_large_weights() {
+        let mut graph = Graph::new(BTreeMap::new());
+        graph.connect_weighted(1, 2, u64::MAX - 1);
+        graph.connect_weighted(2, 3, 5);
+        graph.connect_weighted(1, 4, 1);
+        graph.connect_weighted(4, 3, 2);
+        graph.connect_weighted(2, 5, 1);
+        graph.connect_weighted(5, 6, 1);
+
+        assert_eq!(
+            graph.dijkstra(1),
+            BTreeMap::from([(1, 0), (2, u64::MAX - 1), (3, 3), (4, 1), (5, u64::MAX)])
+        );
+        assert_eq!(graph.dijkstra_path(1, 3), Some((3, vec![1, 4, 3])));
+        assert_eq!(graph.dijkstra_path(1, 6), None);
+    }
+}
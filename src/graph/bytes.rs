@@ -0,0 +1,204 @@
+use super::{Graph, NodeId};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BytesError {
+    // byte offset where the input stopped making sense
+    Syntax(usize),
+    SelfLoop(NodeId),
+    // an edge points to a node that isn't listed
+    Dangling(NodeId),
+}
+
+// an id as written to the bytes, which fits whichever width ids have
+#[cfg(not(feature = "u64-ids"))]
+fn wide(id: NodeId) -> u64 {
+    u64::from(id)
+}
+
+#[cfg(feature = "u64-ids")]
+fn wide(id: NodeId) -> u64 {
+    id
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        bytes.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    bytes.push(n as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn varint(&mut self) -> Result<u64, BytesError> {
+        let start = self.pos;
+        let mut n = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or(BytesError::Syntax(self.pos))?;
+            self.pos += 1;
+
+            n |= u64::from(byte & 0x7f)
+                .checked_shl(shift)
+                .filter(|part| part >> shift == u64::from(byte & 0x7f))
+                .ok_or(BytesError::Syntax(start))?;
+
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+
+        Err(BytesError::Syntax(start))
+    }
+
+    // `previous` plus a delta, which has to be positive unless there's no previous id yet
+    fn id(&mut self, previous: Option<NodeId>) -> Result<NodeId, BytesError> {
+        let start = self.pos;
+        let delta = self.varint()?;
+
+        let id = match previous {
+            Some(_) if delta == 0 => None,
+            Some(previous) => wide(previous).checked_add(delta),
+            None => Some(delta),
+        };
+
+        id.and_then(|id| NodeId::try_from(id).ok())
+            .ok_or(BytesError::Syntax(start))
+    }
+
+    fn count(&mut self) -> Result<usize, BytesError> {
+        let start = self.pos;
+        // every entry takes at least a byte, so a larger count can't be right
+        usize::try_from(self.varint()?)
+            .ok()
+            .filter(|count| *count <= self.bytes.len() - self.pos)
+            .ok_or(BytesError::Syntax(start))
+    }
+}
+
+impl Graph {
+    // LEB128 varints: the node count, then every node as the difference to the previous id, its
+    // out-degree and its targets the same way, weights aren't stored
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, self.nodes.len() as u64);
+
+        let mut previous = 0;
+        for (id, connected) in self.nodes.iter() {
+            write_varint(&mut bytes, wide(*id - previous));
+            write_varint(&mut bytes, connected.len() as u64);
+            previous = *id;
+
+            let mut previous = 0;
+            for to in connected.iter() {
+                write_varint(&mut bytes, wide(*to - previous));
+                previous = *to;
+            }
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Graph, BytesError> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        let mut nodes = BTreeMap::new();
+        let mut previous = None;
+        for _ in 0..reader.count()? {
+            let id = reader.id(previous)?;
+            previous = Some(id);
+
+            let mut connected = BTreeSet::new();
+            let mut previous = None;
+            for _ in 0..reader.count()? {
+                let to = reader.id(previous)?;
+                previous = Some(to);
+                connected.insert(to);
+            }
+
+            nodes.insert(id, connected);
+        }
+
+        if reader.pos != bytes.len() {
+            return Err(BytesError::Syntax(reader.pos));
+        }
+
+        for (from, connected) in nodes.iter() {
+            if connected.contains(from) {
+                return Err(BytesError::SelfLoop(*from));
+            }
+
+            if let Some(dangling) = connected.iter().find(|to| !nodes.contains_key(to)) {
+                return Err(BytesError::Dangling(*dangling));
+            }
+        }
+
+        Ok(Graph::new(nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::create_graph;
+
+    #[test]
+    fn round_trip() {
+        let mut graph = create_graph! { 1 => 2, 300; 300 => 1; 70000 => 2 };
+        graph.add_node(5);
+
+        assert_eq!(Graph::from_bytes(&graph.to_bytes()), Ok(graph));
+        assert_eq!(
+            Graph::from_bytes(&Graph::new(BTreeMap::new()).to_bytes()),
+            Ok(Graph::new(BTreeMap::new()))
+        );
+    }
+
+    #[test]
+    fn large_ids_round_trip() {
+        let graph = create_graph! { NodeId::MAX => 0 };
+
+        assert_eq!(Graph::from_bytes(&graph.to_bytes()), Ok(graph));
+    }
+
+    #[test]
+    fn malformed() {
+        let bytes = create_graph! { 1 => 2 }.to_bytes();
+
+        assert_eq!(
+            Graph::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(BytesError::Syntax(5))
+        );
+        assert_eq!(
+            Graph::from_bytes(&[bytes.as_slice(), &[0]].concat()),
+            Err(BytesError::Syntax(bytes.len()))
+        );
+        // the second node repeats the first one
+        assert_eq!(
+            Graph::from_bytes(&[2, 1, 0, 0, 0]),
+            Err(BytesError::Syntax(3))
+        );
+        // a varint that doesn't end
+        assert_eq!(Graph::from_bytes(&[0x80; 12]), Err(BytesError::Syntax(0)));
+        assert_eq!(
+            Graph::from_bytes(&[1, 1, 1, 1]),
+            Err(BytesError::SelfLoop(1))
+        );
+        assert_eq!(
+            Graph::from_bytes(&[1, 1, 1, 2]),
+            Err(BytesError::Dangling(2))
+        );
+        assert_eq!(Graph::from_bytes(&[]), Err(BytesError::Syntax(0)));
+    }
+}
@@ -0,0 +1,75 @@
+use super::{Graph, NodeId};
+use nom::{
+    bytes::complete::tag,
+    character::complete::{digit1, line_ending, space0},
+    combinator::{all_consuming, map_res, opt},
+    multi::many0,
+    sequence::{delimited, preceded, terminated},
+    IResult, Parser,
+};
+use std::{collections::BTreeMap, format, string::String, vec::Vec};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CsvError {
+    // byte offset where the input stopped making sense
+    Syntax(usize),
+    SelfLoop(NodeId),
+}
+
+fn id(input: &str) -> IResult<&str, NodeId> {
+    delimited(space0, map_res(digit1, |n: &str| n.parse()), space0)(input)
+}
+
+// `from,to` for an edge or a lone `id` for a node without any
+fn row(input: &str) -> IResult<&str, Option<(NodeId, Option<NodeId>)>> {
+    terminated(
+        opt(id.and(opt(preceded(tag(","), id)))),
+        space0.and(line_ending),
+    )(input)
+}
+
+fn rows(input: &str) -> IResult<&str, Vec<(NodeId, Option<NodeId>)>> {
+    many0(row)
+        .and(opt(id.and(opt(preceded(tag(","), id)))))
+        .map(|(rows, last)| rows.into_iter().chain([last]).flatten().collect())
+        .parse(input)
+}
+
+impl Graph {
+    // one `from,to` row per edge and a lone `id` row per node without edges
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+
+        for (from, connected) in self.nodes.iter() {
+            if connected.is_empty() && self.to(*from).next().is_none() {
+                csv.push_str(&format!("{from}\n"));
+            }
+
+            for to in connected.iter() {
+                csv.push_str(&format!("{from},{to}\n"));
+            }
+        }
+
+        csv
+    }
+
+    pub fn from_csv(input: &str) -> Result<Graph, CsvError> {
+        let (_, rows) = all_consuming(rows).parse(input).map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                CsvError::Syntax(input.len() - e.input.len())
+            }
+            nom::Err::Incomplete(_) => CsvError::Syntax(input.len()),
+        })?;
+
+        let mut graph = Graph::new(BTreeMap::new());
+        for (from, to) in rows {
+            match to {
+                Some(to) if !graph.connect(from, to) => return Err(CsvError::SelfLoop(from)),
+                Some(_) => {}
+                None => graph.add_node(from),
+            }
+        }
+
+        Ok(graph)
+    }
+}
@@ -0,0 +1,62 @@
+use super::{Graph, NodeId};
+use crate::cycle::Cycle;
+use alloc::{
+    collections::{btree_map, btree_set, BTreeSet},
+    vec,
+    vec::Vec,
+};
+
+// Depth first search for every elementary cycle, each one is searched for from its smallest node
+// only, so it is found exactly once. The search is paused after every cycle found.
+struct Cycles<'a> {
+    graph: &'a Graph,
+    starts: btree_map::Keys<'a, NodeId, BTreeSet<NodeId>>,
+    path: Vec<NodeId>,
+    stack: Vec<btree_set::Iter<'a, NodeId>>,
+}
+
+impl Iterator for Cycles<'_> {
+    type Item = Cycle;
+
+    fn next(&mut self) -> Option<Cycle> {
+        loop {
+            let Some(connected) = self.stack.last_mut() else {
+                let start = *self.starts.next()?;
+                self.path = vec![start];
+                self.stack.push(self.graph.nodes[&start].iter());
+                continue;
+            };
+
+            let start = self.path[0];
+            match connected.next() {
+                Some(node) if *node == start => return Cycle::new(self.path.clone()),
+                Some(node) if *node > start && !self.path.contains(node) => {
+                    self.path.push(*node);
+                    self.stack.push(self.graph.nodes[node].iter());
+                }
+                Some(_) => {}
+                None => {
+                    self.stack.pop();
+                    self.path.pop();
+                }
+            }
+        }
+    }
+}
+
+impl Graph {
+    // a snapshot, the graph can't be changed while the iterator is alive
+    pub fn cycles_iter(&self) -> impl Iterator<Item = Cycle> + '_ {
+        Cycles {
+            graph: self,
+            starts: self.nodes.keys(),
+            path: Vec::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    // every elementary cycle, there can be exponentially many
+    pub fn all_cycles(&self) -> Vec<Cycle> {
+        self.cycles_iter().collect()
+    }
+}
@@ -0,0 +1,139 @@
+use super::{Graph, NodeId};
+use itertools::Itertools;
+use nom::{
+    bytes::complete::{tag, take_until},
+    character::complete::{alphanumeric1, digit1, multispace0},
+    combinator::{all_consuming, map_res, opt},
+    multi::{many0, separated_list1},
+    sequence::{delimited, terminated, tuple},
+    IResult, Parser,
+};
+use std::{
+    collections::BTreeMap,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DotError {
+    // byte offset where the input stopped making sense
+    Syntax(usize),
+    SelfLoop(NodeId),
+}
+
+fn token<'a>(t: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    delimited(multispace0, tag(t), multispace0)
+}
+
+fn id(input: &str) -> IResult<&str, NodeId> {
+    delimited(
+        multispace0,
+        map_res(digit1, |n: &str| n.parse()),
+        multispace0,
+    )(input)
+}
+
+// attributes aren't supported, they're skipped over
+fn attributes(input: &str) -> IResult<&str, ()> {
+    delimited(token("["), take_until("]"), token("]"))
+        .map(|_| ())
+        .parse(input)
+}
+
+// a lone node or a chain of edges
+fn statement(input: &str) -> IResult<&str, Vec<NodeId>> {
+    terminated(
+        separated_list1(token("->"), id),
+        tuple((opt(attributes), opt(token(";")))),
+    )(input)
+}
+
+fn digraph(input: &str) -> IResult<&str, Vec<Vec<NodeId>>> {
+    delimited(
+        tuple((
+            token("digraph"),
+            opt(delimited(multispace0, alphanumeric1, multispace0)),
+            token("{"),
+        )),
+        many0(statement),
+        token("}"),
+    )(input)
+}
+
+impl Graph {
+    pub fn to_dot(&self) -> String {
+        let mut dot = "digraph {\n".to_string();
+
+        for node in self.nodes.keys() {
+            dot.push_str(&format!("    {node};\n"));
+        }
+
+        for (from, connected) in self.nodes.iter() {
+            for to in connected.iter() {
+                dot.push_str(&format!("    {from} -> {to};\n"));
+            }
+        }
+
+        dot.push('}');
+        dot
+    }
+
+    pub fn from_dot(input: &str) -> Result<Graph, DotError> {
+        let (_, statements) = all_consuming(digraph).parse(input).map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                DotError::Syntax(input.len() - e.input.len())
+            }
+            nom::Err::Incomplete(_) => DotError::Syntax(input.len()),
+        })?;
+
+        let mut graph = Graph::new(BTreeMap::new());
+        for chain in statements {
+            graph.with_node(chain[0]);
+
+            for (from, to) in chain.iter().tuple_windows() {
+                if !graph.connect(*from, *to) {
+                    return Err(DotError::SelfLoop(*from));
+                }
+            }
+        }
+
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::create_graph;
+
+    #[test]
+    fn round_trip() {
+        let mut graph = create_graph! { 1 => 2, 3; 3 => 1 };
+        graph.add_node(7);
+
+        assert_eq!(Graph::from_dot(&graph.to_dot()), Ok(graph));
+    }
+
+    #[test]
+    fn attributes_and_chains() {
+        let graph =
+            Graph::from_dot("digraph deps {\n 1 [label=one];\n 1 -> 2 -> 3 [color=red]\n 4 }");
+
+        let mut expected = create_graph! { 1 => 2; 2 => 3 };
+        expected.add_node(4);
+        assert_eq!(graph, Ok(expected));
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            Graph::from_dot("digraph { 1 -> 1 }"),
+            Err(DotError::SelfLoop(1))
+        );
+        assert!(matches!(
+            Graph::from_dot("digraph { a -> b }"),
+            Err(DotError::Syntax(_))
+        ));
+    }
+}
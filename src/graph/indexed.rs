@@ -0,0 +1,136 @@
+use super::{Graph, NodeId};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec::Vec,
+};
+
+// A graph that also keeps the incoming edges of every node, so `to` doesn't have to scan the
+// whole graph. It's kept up to date by going through `connect` and `disconnect` here only.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct IndexedGraph {
+    graph: Graph,
+    parents: BTreeMap<NodeId, BTreeSet<NodeId>>,
+}
+
+impl IndexedGraph {
+    pub fn new(graph: Graph) -> Self {
+        let mut parents: BTreeMap<_, _> = graph
+            .nodes
+            .keys()
+            .map(|id| (*id, BTreeSet::new()))
+            .collect();
+
+        for (from, to) in graph.edges() {
+            parents.get_mut(&to).unwrap().insert(from);
+        }
+
+        IndexedGraph { graph, parents }
+    }
+
+    pub fn add_node(&mut self, id: NodeId) {
+        self.graph.add_node(id);
+        self.parents.entry(id).or_default();
+    }
+
+    pub fn connect(&mut self, from: NodeId, to: NodeId) -> bool {
+        if !self.graph.connect(from, to) {
+            return false;
+        }
+
+        self.parents.entry(from).or_default();
+        self.parents.entry(to).or_default().insert(from);
+
+        true
+    }
+
+    pub fn disconnect(&mut self, from: NodeId, to: NodeId) {
+        self.graph.disconnect(from, to);
+
+        if let Some(parents) = self.parents.get_mut(&to) {
+            parents.remove(&from);
+        }
+    }
+
+    pub fn from(&self, id: NodeId) -> Option<&BTreeSet<NodeId>> {
+        self.graph.from(id)
+    }
+
+    pub fn to(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.parents.get(&id).into_iter().flatten().copied()
+    }
+
+    // same as `Graph::find_ends`, without scanning the graph for every node
+    pub fn find_ends(&self) -> (Vec<NodeId>, Vec<NodeId>) {
+        let (mut starts, mut ends) = (Vec::new(), Vec::new());
+
+        for (id, connected) in self.graph.nodes.iter() {
+            if connected.is_empty() {
+                ends.push(*id);
+            }
+
+            if self.parents[id].is_empty() {
+                starts.push(*id);
+            }
+        }
+
+        (starts, ends)
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn into_graph(self) -> Graph {
+        self.graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::create_graph;
+    use alloc::vec;
+
+    #[test]
+    fn keeps_parents_up_to_date() {
+        let mut indexed = IndexedGraph::new(create_graph! { 1 => 2, 3; 2 => 3 });
+        assert_eq!(indexed.to(3).collect::<Vec<_>>(), vec![1, 2]);
+
+        assert!(indexed.connect(4, 3));
+        assert!(!indexed.connect(3, 3));
+        indexed.disconnect(1, 3);
+        indexed.add_node(5);
+
+        assert_eq!(indexed.to(3).collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(indexed.to(5).count(), 0);
+        assert_eq!(indexed, IndexedGraph::new(indexed.graph().clone()));
+        assert_eq!(indexed.find_ends(), indexed.graph().find_ends());
+
+        let mut expected = create_graph! { 1 => 2; 2 => 3; 4 => 3 };
+        expected.add_node(5);
+        assert_eq!(indexed.into_graph(), expected);
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with `--release -- --ignored --nocapture`"]
+    fn bench_find_ends() {
+        let mut graph = Graph::with_nodes(0..5_000);
+        for _ in 0..20_000 {
+            graph.connect(
+                rand::random::<NodeId>() % 5_000,
+                rand::random::<NodeId>() % 5_000,
+            );
+        }
+
+        let start = std::time::Instant::now();
+        let expected = graph.find_ends();
+        std::println!("graph: {:?}", start.elapsed());
+
+        let indexed = IndexedGraph::new(graph);
+        let start = std::time::Instant::now();
+        let ends = indexed.find_ends();
+        std::println!("indexed: {:?}", start.elapsed());
+
+        assert_eq!(ends, expected);
+    }
+}
@@ -0,0 +1,124 @@
+use super::{Graph, NodeId};
+use itertools::Itertools;
+use nom::{
+    character::complete::{char, digit1, multispace0},
+    combinator::{all_consuming, map_res},
+    multi::separated_list0,
+    sequence::{delimited, separated_pair},
+    IResult, Parser,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    format,
+    string::String,
+    vec::Vec,
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum JsonError {
+    // byte offset where the input stopped making sense
+    Syntax(usize),
+    SelfLoop(NodeId),
+}
+
+fn token<'a>(c: char) -> impl FnMut(&'a str) -> IResult<&'a str, char> {
+    delimited(multispace0, char(c), multispace0)
+}
+
+fn number(input: &str) -> IResult<&str, NodeId> {
+    delimited(
+        multispace0,
+        map_res(digit1, |n: &str| n.parse()),
+        multispace0,
+    )(input)
+}
+
+fn object(input: &str) -> IResult<&str, Vec<(NodeId, Vec<NodeId>)>> {
+    delimited(
+        token('{'),
+        separated_list0(
+            token(','),
+            separated_pair(
+                delimited(token('"'), number, token('"')),
+                token(':'),
+                delimited(token('['), separated_list0(token(','), number), token(']')),
+            ),
+        ),
+        token('}'),
+    )(input)
+}
+
+impl Graph {
+    pub fn to_json(&self) -> String {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(n, c)| format!("\"{n}\":[{}]", c.iter().join(",")))
+            .join(",");
+
+        format!("{{{nodes}}}")
+    }
+
+    pub fn from_json(input: &str) -> Result<Graph, JsonError> {
+        let (_, parsed) = all_consuming(object).parse(input).map_err(|e| match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => {
+                JsonError::Syntax(input.len() - e.input.len())
+            }
+            nom::Err::Incomplete(_) => JsonError::Syntax(input.len()),
+        })?;
+
+        let mut nodes = BTreeMap::new();
+        for (id, connected_to) in parsed {
+            for to in connected_to.iter() {
+                if *to == id {
+                    return Err(JsonError::SelfLoop(id));
+                }
+
+                nodes.entry(*to).or_insert_with(BTreeSet::new);
+            }
+
+            nodes
+                .entry(id)
+                .or_insert_with(BTreeSet::new)
+                .extend(connected_to);
+        }
+
+        Ok(Graph::new(nodes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::create_graph;
+
+    #[test]
+    fn round_trip() {
+        let mut graph = create_graph! { 1 => 2, 3; 3 => 1 };
+        graph.add_node(7);
+
+        assert_eq!(graph.to_json(), r#"{"1":[2,3],"2":[],"3":[1],"7":[]}"#);
+        assert_eq!(Graph::from_json(&graph.to_json()), Ok(graph));
+        assert_eq!(Graph::from_json(" { } "), Ok(Graph::new(BTreeMap::new())));
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(matches!(
+            Graph::from_json(r#"{"1":[2,]}"#),
+            Err(JsonError::Syntax(_))
+        ));
+        assert!(matches!(
+            Graph::from_json(r#"{"1":[2]"#),
+            Err(JsonError::Syntax(_))
+        ));
+        assert!(matches!(
+            Graph::from_json(r#"{"a":[]}"#),
+            Err(JsonError::Syntax(_))
+        ));
+        assert_eq!(
+            Graph::from_json(r#"{"1":[1]}"#),
+            Err(JsonError::SelfLoop(1))
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use super::{CsvError, DotError, Graph, JsonError};
+use crate::parsing::{parse_graph, ParseError};
+use std::{fs, io, path::Path};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Format {
+    Dot,
+    Csv,
+    Json,
+    // the format documented in `parsing`
+    Text,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Dot(DotError),
+    Csv(CsvError),
+    Json(JsonError),
+    Text(ParseError),
+}
+
+impl Graph {
+    pub fn load(path: &Path, format: Format) -> Result<Graph, LoadError> {
+        let input = fs::read_to_string(path).map_err(LoadError::Io)?;
+
+        match format {
+            Format::Dot => Graph::from_dot(&input).map_err(LoadError::Dot),
+            Format::Csv => Graph::from_csv(&input).map_err(LoadError::Csv),
+            Format::Json => Graph::from_json(&input).map_err(LoadError::Json),
+            Format::Text => parse_graph(&input).map_err(LoadError::Text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::create_graph;
+    use std::{format, string::ToString};
+
+    #[test]
+    fn every_format_loads_the_same_graph() {
+        let mut graph = create_graph! { 1 => 2, 3; 3 => 1 };
+        graph.add_node(7);
+
+        let files = [
+            (Format::Dot, graph.to_dot()),
+            (Format::Csv, graph.to_csv()),
+            (Format::Json, graph.to_json()),
+            (Format::Text, "1 -> (2; 3) 3 -> 1 7".to_string()),
+        ];
+
+        for (i, (format, contents)) in files.iter().enumerate() {
+            let path = std::env::temp_dir().join(format!("graphs-load-{}-{i}", std::process::id()));
+            fs::write(&path, contents).unwrap();
+            let loaded = Graph::load(&path, *format);
+            fs::remove_file(&path).unwrap();
+
+            assert_eq!(loaded.unwrap(), graph, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = std::env::temp_dir().join("graphs-load-missing");
+
+        assert!(matches!(
+            Graph::load(&path, Format::Json),
+            Err(LoadError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,37 @@
+use super::Graph;
+use alloc::{format, string::String};
+
+impl Graph {
+    // a `flowchart TD` declaring every node before the edges, so isolated ones show up too
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart TD\n");
+
+        for node in self.nodes.keys() {
+            mermaid.push_str(&format!("    {node}\n"));
+        }
+
+        for (from, connected) in self.nodes.iter() {
+            for to in connected.iter() {
+                mermaid.push_str(&format!("    {from} --> {to}\n"));
+            }
+        }
+
+        mermaid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::create_graph;
+
+    #[test]
+    fn flowchart() {
+        let mut graph = create_graph! { 1 => 2, 3 };
+        graph.add_node(4);
+
+        assert_eq!(
+            graph.to_mermaid(),
+            "flowchart TD\n    1\n    2\n    3\n    4\n    1 --> 2\n    1 --> 3\n"
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use super::{Graph, NodeId};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    thread,
+    vec::Vec,
+};
+
+impl Graph {
+    // breadth first order of every weakly connected component, starting at its smallest node, the
+    // components are only told apart with a union-find over the edges, the searches themselves are
+    // split between threads since they don't share anything
+    pub fn bfs_all_components(&self) -> Vec<Vec<NodeId>> {
+        fn find(parents: &mut BTreeMap<NodeId, NodeId>, node: NodeId) -> NodeId {
+            let parent = parents[&node];
+            if parent == node {
+                return node;
+            }
+
+            let root = find(parents, parent);
+            parents.insert(node, root);
+            root
+        }
+
+        let mut parents: BTreeMap<_, _> = self.nodes.keys().map(|id| (*id, *id)).collect();
+        for (from, to) in self.edges() {
+            let (from_root, to_root) = (find(&mut parents, from), find(&mut parents, to));
+            if from_root != to_root {
+                parents.insert(from_root, to_root);
+            }
+        }
+
+        // nodes come in ascending order, so the first one of every component is its smallest
+        let mut seen = BTreeSet::new();
+        let starts = self
+            .nodes
+            .keys()
+            .filter(|id| seen.insert(find(&mut parents, **id)))
+            .copied()
+            .collect::<Vec<_>>();
+        if starts.is_empty() {
+            return Vec::new();
+        }
+
+        let adjacency = self.undirected_adjacency();
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk = starts.len().div_ceil(threads);
+
+        thread::scope(|scope| {
+            let handles = starts
+                .chunks(chunk)
+                .map(|starts| {
+                    let adjacency = &adjacency;
+                    scope.spawn(move || {
+                        starts
+                            .iter()
+                            .map(|start| Graph::bfs(adjacency, *start))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::create_graph;
+    use std::{println, time::Instant, vec};
+
+    fn serial(graph: &Graph) -> Vec<Vec<NodeId>> {
+        let adjacency = graph.undirected_adjacency();
+
+        graph
+            .weakly_connected_components()
+            .iter()
+            .map(|component| Graph::bfs(&adjacency, component[0]))
+            .collect()
+    }
+
+    // `size` nodes in every component, each node pointing back at a random earlier one
+    fn forest(components: NodeId, size: NodeId) -> Graph {
+        let mut graph = Graph::with_nodes(0..components * size);
+        for component in 0..components {
+            let start = component * size;
+            for node in start + 1..start + size {
+                graph.connect(node, rand::random::<NodeId>() % (node - start) + start);
+            }
+        }
+
+        graph
+    }
+
+    #[test]
+    fn matches_serial() {
+        let graph = create_graph! { 5 => 1; 1 => 3; 2 => 4; 6 => 4; 4 => 8 };
+
+        assert_eq!(
+            graph.bfs_all_components(),
+            vec![vec![1, 3, 5], vec![2, 4, 6, 8]]
+        );
+        assert_eq!(graph.bfs_all_components(), serial(&graph));
+
+        let graph = forest(200, 30);
+        assert_eq!(graph.bfs_all_components(), serial(&graph));
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with `--release -- --ignored --nocapture`"]
+    fn bench_against_serial() {
+        let graph = forest(20_000, 50);
+
+        let start = Instant::now();
+        let expected = serial(&graph);
+        println!("serial: {:?}", start.elapsed());
+
+        let start = Instant::now();
+        let parallel = graph.bfs_all_components();
+        println!("parallel: {:?}", start.elapsed());
+
+        assert_eq!(parallel, expected);
+    }
+}
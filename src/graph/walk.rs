@@ -0,0 +1,80 @@
+use super::{Graph, NodeId};
+use alloc::{vec, vec::Vec};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum WalkError {
+    // the node isn't in the graph at all
+    Absent(NodeId),
+    NoEdge(NodeId, NodeId),
+}
+
+// follows edges one at a time, refusing any step that isn't along an existing edge
+pub struct Walk<'a> {
+    graph: &'a Graph,
+    path: Vec<NodeId>,
+}
+
+impl Walk<'_> {
+    pub fn step(&mut self, to: NodeId) -> Result<(), WalkError> {
+        let current = self.current();
+        let connected = self.graph.from(current).ok_or(WalkError::Absent(current))?;
+
+        if !self.graph.nodes.contains_key(&to) {
+            return Err(WalkError::Absent(to));
+        }
+
+        if !connected.contains(&to) {
+            return Err(WalkError::NoEdge(current, to));
+        }
+
+        self.path.push(to);
+
+        Ok(())
+    }
+
+    pub fn current(&self) -> NodeId {
+        *self.path.last().unwrap()
+    }
+
+    // every node visited so far, starting with the start
+    pub fn path(&self) -> &[NodeId] {
+        &self.path
+    }
+}
+
+impl Graph {
+    pub fn walk(&self, start: NodeId) -> Walk<'_> {
+        Walk {
+            graph: self,
+            path: vec![start],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::create_graph;
+
+    #[test]
+    fn follows_edges() {
+        let graph = create_graph! { 1 => 2; 2 => 3; 3 => 1 };
+        let mut walk = graph.walk(1);
+
+        assert_eq!(walk.step(2), Ok(()));
+        assert_eq!(walk.step(3), Ok(()));
+        assert_eq!(walk.step(1), Ok(()));
+        assert_eq!(walk.path(), [1, 2, 3, 1]);
+    }
+
+    #[test]
+    fn rejected_steps() {
+        let graph = create_graph! { 1 => 2; 2 => 3 };
+        let mut walk = graph.walk(1);
+
+        assert_eq!(walk.step(3), Err(WalkError::NoEdge(1, 3)));
+        assert_eq!(walk.step(9), Err(WalkError::Absent(9)));
+        assert_eq!(walk.path(), [1]);
+        assert_eq!(graph.walk(9).step(1), Err(WalkError::Absent(9)));
+    }
+}
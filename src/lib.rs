@@ -0,0 +1,12 @@
+#![no_std]
+
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+pub mod graph;
+pub mod cycle;
+#[cfg(feature = "std")]
+pub mod parsing;
+pub mod online_dag;
+pub mod dag;
@@ -1,8 +1,6 @@
-use graph::create_graph;
+extern crate alloc;
 
-mod graph;
-mod cycle;
-mod parsing;
+use graphs::create_graph;
 
 fn main() {
     let graph = create_graph! {
@@ -0,0 +1,212 @@
+use crate::{
+    cycle::Cycle,
+    graph::{Graph, NodeId},
+};
+use alloc::{
+    collections::{BTreeMap, BTreeSet},
+    vec,
+    vec::Vec,
+};
+use itertools::Itertools;
+
+// Keeps a topological order up to date while edges are added (Pearce-Kelly), so an edge closing a
+// cycle is rejected without searching the whole graph.
+pub struct OnlineDag {
+    graph: Graph,
+    parents: BTreeMap<NodeId, BTreeSet<NodeId>>,
+    order: BTreeMap<NodeId, usize>,
+}
+
+impl Default for OnlineDag {
+    fn default() -> Self {
+        OnlineDag {
+            graph: Graph::new(BTreeMap::new()),
+            parents: BTreeMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+}
+
+impl OnlineDag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId) -> Result<(), Cycle> {
+        // same as `Graph::connect`, self-loops aren't stored at all
+        if from == to {
+            return Ok(());
+        }
+
+        for node in [from, to] {
+            let next = self.order.len();
+            self.order.entry(node).or_insert(next);
+        }
+
+        let (lower, upper) = (self.order[&to], self.order[&from]);
+        if lower < upper {
+            let forward = self.forward(to, from, upper)?;
+            let backward = self.backward(from, lower);
+            self.reorder(backward, forward);
+        }
+
+        self.graph.connect(from, to);
+        self.parents.entry(to).or_default().insert(from);
+
+        Ok(())
+    }
+
+    // nodes reachable from `start` that are ordered before `upper`, or the cycle if `target` is one
+    fn forward(&self, start: NodeId, target: NodeId, upper: usize) -> Result<Vec<NodeId>, Cycle> {
+        let mut previous = BTreeMap::from([(start, start)]);
+        let mut stack = vec![start];
+
+        while let Some(node) = stack.pop() {
+            for child in self.graph.from(node).into_iter().flatten() {
+                if *child == target {
+                    let mut path = vec![node];
+                    while *path.last().unwrap() != start {
+                        path.push(previous[path.last().unwrap()]);
+                    }
+                    path.reverse();
+                    path.push(target);
+
+                    return Err(Cycle::new(path).unwrap());
+                }
+
+                if self.order[child] < upper && !previous.contains_key(child) {
+                    previous.insert(*child, node);
+                    stack.push(*child);
+                }
+            }
+        }
+
+        Ok(previous.into_keys().collect())
+    }
+
+    // nodes reaching `start` that are ordered after `lower`
+    fn backward(&self, start: NodeId, lower: usize) -> Vec<NodeId> {
+        let mut visited = BTreeSet::from([start]);
+        let mut stack = vec![start];
+
+        while let Some(node) = stack.pop() {
+            for parent in self.parents.get(&node).into_iter().flatten() {
+                if self.order[parent] > lower && visited.insert(*parent) {
+                    stack.push(*parent);
+                }
+            }
+        }
+
+        visited.into_iter().collect()
+    }
+
+    // everything that reaches the new edge goes before everything reachable from it, reusing the
+    // same slots of the order
+    fn reorder(&mut self, backward: Vec<NodeId>, forward: Vec<NodeId>) {
+        let slots = backward
+            .iter()
+            .chain(forward.iter())
+            .map(|node| self.order[node])
+            .sorted()
+            .collect_vec();
+
+        let nodes = backward
+            .into_iter()
+            .sorted_by_key(|node| self.order[node])
+            .chain(forward.into_iter().sorted_by_key(|node| self.order[node]))
+            .collect_vec();
+
+        for (node, slot) in nodes.into_iter().zip(slots) {
+            self.order.insert(node, slot);
+        }
+    }
+
+    pub fn topological_order(&self) -> Vec<NodeId> {
+        self.order
+            .iter()
+            .sorted_by_key(|(_, slot)| **slot)
+            .map(|(node, _)| *node)
+            .collect()
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn into_graph(self) -> Graph {
+        self.graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_topological(dag: &OnlineDag) -> bool {
+        let position: BTreeMap<_, _> = dag
+            .topological_order()
+            .into_iter()
+            .enumerate()
+            .map(|(i, node)| (node, i))
+            .collect();
+
+        dag.graph()
+            .edges()
+            .all(|(from, to)| position[&from] < position[&to])
+    }
+
+    #[test]
+    fn keeps_a_topological_order() {
+        let mut dag = OnlineDag::new();
+        for (from, to) in [(3, 4), (1, 2), (4, 1), (2, 5), (5, 6), (3, 6)] {
+            assert_eq!(dag.add_edge(from, to), Ok(()));
+            assert!(is_topological(&dag));
+        }
+
+        assert_eq!(dag.topological_order().len(), 6);
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let mut dag = OnlineDag::new();
+        dag.add_edge(1, 2).unwrap();
+        dag.add_edge(2, 3).unwrap();
+        let before = dag.graph().clone();
+
+        assert_eq!(dag.add_edge(3, 1), Err(Cycle::new(vec![1, 2, 3]).unwrap()));
+        assert_eq!(dag.graph(), &before);
+        assert_eq!(dag.add_edge(2, 2), Ok(()));
+        assert!(is_topological(&dag));
+    }
+
+    #[test]
+    #[ignore = "benchmark, run with `--release -- --ignored --nocapture`"]
+    fn bench_against_find_cycle() {
+        let edges = (0..3_000)
+            .map(|_| {
+                let (a, b) = (
+                    rand::random::<NodeId>() % 1_000,
+                    rand::random::<NodeId>() % 1_000,
+                );
+                (a.min(b), a.max(b))
+            })
+            .collect_vec();
+
+        let start = std::time::Instant::now();
+        let mut naive = Graph::new(BTreeMap::new());
+        for (from, to) in edges.iter() {
+            naive.connect(*from, *to);
+            assert!(naive.find_cycle().is_none());
+        }
+        std::println!("connect and find_cycle: {:?}", start.elapsed());
+
+        let start = std::time::Instant::now();
+        let mut dag = OnlineDag::new();
+        for (from, to) in edges.iter() {
+            dag.add_edge(*from, *to).unwrap();
+        }
+        std::println!("online: {:?}", start.elapsed());
+
+        assert_eq!(dag.graph(), &naive);
+    }
+}
@@ -5,84 +5,248 @@
 /// 1 -> { ->3 > 2 > (6 -> *5 -> 4) > 7 > (8 -> 9)-> }
 ///
 /// ## Syntax
-/// connection      :== <exportable> (<connection-type> <expresion>)+
+/// document        :== <exportable>+
+/// connection      :== <term> (<connection-type> <term>)+
 /// connection-type :== "->" | "<-" | "<->"
-/// list            :== "(" (<exportable> ";")* <expression> ")"
-/// full            :== "[ " <exportable> ("," <expresion>)+ " ]"
-/// cycle           :== "{" <exportable> ("->" <expression>)+ "}"
-/// exportable      :== "*" <expression>
-/// expression      :== <node> | <list> | <full> | <cycle> | <connection>
+/// list            :== "(" (<exportable> ";")* <exportable> ")"
+/// full            :== "[" <exportable> ("," <exportable>)* "]"
+/// cycle           :== "{" "->" <exportable> (">" <exportable>)+ "->" "}"
+/// exportable      :== "*"? <expression>
+/// term            :== "*"? (<range> | <node> | <list> | <full> | <cycle>)
+/// expression      :== <connection> | <range> | <node> | <list> | <full> | <cycle>
+/// range           :== <number> ".." <number>
 /// node            :== <number> | <name>
 /// number          :== <digit>+
 /// name            :== "\"" <char>+ "\""
+///
+/// Whitespace is allowed between any two tokens and separates the statements of a document.
+/// Comments start with `#` and run to the end of the line, they count as whitespace. A `#` inside
+/// of a name is part of the name.
+///
+/// ## Semantics
+/// Every expression exports the nodes that connections to it attach to. Those are the items marked
+/// with `*`, or all of its items when none are marked. A connection links every exported node of
+/// one side to every exported node of the other, `a <- b` is the edge `b -> a` and `a <-> b` are
+/// both. A list only groups, a full connects all of its items with each other and a cycle connects
+/// each item to the next one and the last one back to the first. A range `1..4` stands for the
+/// nodes 1, 2 and 3, the same as a list of them, its end has to be larger than its start. Names get
+/// ids larger than any number in the document.
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_while1},
-    multi::separated_list1,
-    sequence::{delimited, pair, preceded, terminated},
+    bytes::complete::{tag, take_till, take_while1},
+    character::complete::{digit1, multispace1},
+    combinator::{all_consuming, eof, map_res, verify},
+    multi::{many0, many1, separated_list1},
+    sequence::{delimited, pair, preceded, separated_pair, terminated},
     IResult, Parser,
 };
 
-enum Node {
+use std::{
+    boxed::Box,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+
+use crate::graph::{Graph, NodeId};
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Node {
     Name(String),
-    Number(u32),
+    Number(NodeId),
+}
+
+impl Node {
+    // a quoted `"name"` or a bare number, surrounding whitespace is ignored
+    pub fn parse(input: &str) -> Option<Node> {
+        all_consuming(delimited(space, node, space))(input)
+            .ok()
+            .map(|(_, node)| node)
+    }
 }
 
-enum ConnectionType {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ConnectionType {
     Forward,
     Backward,
     Both,
 }
 
-struct Connection {
-    left: Exportable,
-    rest: Vec<(ConnectionType, Expression)>,
+#[derive(Debug)]
+pub struct Connection {
+    left: Box<Exportable>,
+    rest: Vec<(ConnectionType, Exportable)>,
 }
 
-struct List(Vec<Exportable>);
+impl Connection {
+    pub fn new(left: Exportable, rest: Vec<(ConnectionType, Exportable)>) -> Self {
+        Connection {
+            left: Box::new(left),
+            rest,
+        }
+    }
 
-struct Full(Vec<Exportable>);
+    pub fn left(&self) -> &Exportable {
+        &self.left
+    }
 
-struct Cycle(Vec<Exportable>);
+    pub fn rest(&self) -> &[(ConnectionType, Exportable)] {
+        &self.rest
+    }
+}
+
+#[derive(Debug)]
+pub struct List(pub Vec<Exportable>);
+
+#[derive(Debug)]
+pub struct Full(pub Vec<Exportable>);
 
-enum Expression {
+#[derive(Debug)]
+pub struct Cycle(pub Vec<Exportable>);
+
+#[derive(Debug)]
+pub enum Expression {
+    Connection(Connection),
+    // every number from the first up to, but not including, the second
+    Range(NodeId, NodeId),
     Node(Node),
     List(List),
     Full(Full),
     Cycle(Cycle),
 }
 
-struct Exportable {
+#[derive(Debug)]
+pub struct Exportable {
     expression: Expression,
     exported: bool,
 }
 
+impl Exportable {
+    pub fn new(expression: Expression, exported: bool) -> Self {
+        Exportable {
+            expression,
+            exported,
+        }
+    }
+
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    pub fn is_exported(&self) -> bool {
+        self.exported
+    }
+
+    pub fn into_expression(self) -> Expression {
+        self.expression
+    }
+}
+
+fn comment(input: &str) -> IResult<&str, &str> {
+    preceded(tag("#"), take_till(|c| c == '\n'))(input)
+}
+
+fn space(input: &str) -> IResult<&str, ()> {
+    many0(alt((multispace1, comment))).map(|_| ()).parse(input)
+}
+
+fn token<'a>(t: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+    delimited(space, tag(t), space)
+}
+
 fn name(input: &str) -> IResult<&str, String> {
     delimited(
         tag("\""),
-        take_while1(|c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+        take_while1(|c: char| c.is_alphanumeric() || "_-.#".contains(c))
             .map(|n: &str| n.to_string()),
         tag("\""),
     )(input)
 }
 
-fn number(input: &str) -> IResult<&str, u32> {
-    take_while1(|c: char| c.is_digit(10))
-        .map(|s: &str| s.parse().unwrap())
-        .parse(input)
+fn number(input: &str) -> IResult<&str, NodeId> {
+    map_res(digit1, |n: &str| n.parse())(input)
+}
+
+// the end has to come after the start, so a range never stands for no nodes at all
+fn range(input: &str) -> IResult<&str, (NodeId, NodeId)> {
+    verify(separated_pair(number, tag(".."), number), |(start, end)| {
+        start < end
+    })(input)
 }
 
 fn node(input: &str) -> IResult<&str, Node> {
     alt((name.map(|n| Node::Name(n)), number.map(|n| Node::Number(n))))(input)
 }
 
+fn connection_type(input: &str) -> IResult<&str, ConnectionType> {
+    alt((
+        token("<->").map(|_| ConnectionType::Both),
+        token("<-").map(|_| ConnectionType::Backward),
+        token("->").map(|_| ConnectionType::Forward),
+    ))(input)
+}
+
+fn connection(input: &str) -> IResult<&str, Connection> {
+    pair(term, many1(pair(connection_type, term)))
+        .map(|(left, rest)| Connection::new(left, rest))
+        .parse(input)
+}
+
+fn list(input: &str) -> IResult<&str, List> {
+    delimited(
+        token("("),
+        separated_list1(token(";"), exportable),
+        token(")"),
+    )
+    .map(List)
+    .parse(input)
+}
+
+fn full(input: &str) -> IResult<&str, Full> {
+    delimited(
+        token("["),
+        separated_list1(token(","), exportable),
+        token("]"),
+    )
+    .map(Full)
+    .parse(input)
+}
+
+// everything but a connection, so connections don't recurse into themselves on the left side
+fn term(input: &str) -> IResult<&str, Exportable> {
+    let inner = || {
+        alt((
+            range.map(|(start, end)| Expression::Range(start, end)),
+            node.map(Expression::Node),
+            list.map(Expression::List),
+            full.map(Expression::Full),
+            cycle.map(Expression::Cycle),
+        ))
+    };
+
+    alt((
+        preceded(token("*"), inner()).map(|e| Exportable {
+            expression: e,
+            exported: true,
+        }),
+        inner().map(|e| Exportable {
+            expression: e,
+            exported: false,
+        }),
+    ))(input)
+}
+
 fn expression(input: &str) -> IResult<&str, Expression> {
-    todo!()
+    alt((
+        connection.map(Expression::Connection),
+        term.map(|t| t.expression),
+    ))(input)
 }
 
 fn exportable(input: &str) -> IResult<&str, Exportable> {
     alt((
-        preceded(tag("*"), expression).map(|e| Exportable {
+        preceded(token("*"), expression).map(|e| Exportable {
             expression: e,
             exported: true,
         }),
@@ -95,19 +259,324 @@ fn exportable(input: &str) -> IResult<&str, Exportable> {
 
 fn cycle(input: &str) -> IResult<&str, Cycle> {
     delimited(
-        tag("{"),
+        token("{"),
         delimited(
-            tag("->"),
+            token("->"),
             pair(
-                terminated(exportable, tag(">")),
-                separated_list1(tag(">"), exportable),
+                terminated(exportable, token(">")),
+                separated_list1(token(">"), exportable),
             )
             .map(|(first, mut rest)| {
                 rest.insert(0, first);
                 Cycle(rest)
             }),
-            tag("->"),
+            token("->"),
         ),
-        tag("}"),
+        token("}"),
     )(input)
 }
+
+fn document(input: &str) -> IResult<&str, Vec<Expression>> {
+    terminated(
+        many1(delimited(space, exportable, space).map(|e| e.expression)),
+        eof,
+    )(input)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    // byte offset where the input stopped making sense
+    Syntax(usize),
+    // every id past the largest number is taken, so there's none left for another name
+    NoIdsForNames,
+}
+
+impl Expression {
+    fn visit_nodes(&self, visit: &mut impl FnMut(&Node)) {
+        match self {
+            Expression::Node(node) => visit(node),
+            Expression::Range(start, end) => {
+                for id in *start..*end {
+                    visit(&Node::Number(id));
+                }
+            }
+            Expression::Connection(connection) => {
+                connection.left.expression.visit_nodes(visit);
+                for (_, right) in connection.rest.iter() {
+                    right.expression.visit_nodes(visit);
+                }
+            }
+            Expression::List(List(items))
+            | Expression::Full(Full(items))
+            | Expression::Cycle(Cycle(items)) => {
+                for item in items.iter() {
+                    item.expression.visit_nodes(visit);
+                }
+            }
+        }
+    }
+}
+
+struct Builder {
+    graph: Graph,
+    names: BTreeMap<String, NodeId>,
+    // larger than every number in the document, so a name never gets the id of a number, even
+    // one it's spelled like, `"42"` and `42` are two different nodes, `None` once ids run out
+    next_name: Option<NodeId>,
+}
+
+impl Builder {
+    fn id(&mut self, node: &Node) -> Result<NodeId, ParseError> {
+        match node {
+            Node::Number(id) => Ok(*id),
+            Node::Name(name) => {
+                if let Some(id) = self.names.get(name) {
+                    return Ok(*id);
+                }
+
+                let id = self.next_name.ok_or(ParseError::NoIdsForNames)?;
+                self.next_name = id.checked_add(1);
+                self.names.insert(name.clone(), id);
+                Ok(id)
+            }
+        }
+    }
+
+    fn link(&mut self, from: &[NodeId], to: &[NodeId]) {
+        for from in from.iter() {
+            for to in to.iter() {
+                self.graph.connect(*from, *to);
+            }
+        }
+    }
+
+    // builds every item and returns the nodes exported by each
+    fn build_items<'a>(
+        &mut self,
+        items: impl Iterator<Item = &'a Exportable>,
+    ) -> Result<Vec<(bool, Vec<NodeId>)>, ParseError> {
+        items
+            .map(|item| Ok((item.exported, self.build(&item.expression)?)))
+            .collect()
+    }
+
+    fn exports(items: Vec<(bool, Vec<NodeId>)>) -> Vec<NodeId> {
+        let any_exported = items.iter().any(|(exported, _)| *exported);
+
+        items
+            .into_iter()
+            .filter(|(exported, _)| *exported || !any_exported)
+            .flat_map(|(_, nodes)| nodes)
+            .collect()
+    }
+
+    fn build(&mut self, expression: &Expression) -> Result<Vec<NodeId>, ParseError> {
+        Ok(match expression {
+            Expression::Node(node) => {
+                let id = self.id(node)?;
+                self.graph.add_node(id);
+                vec![id]
+            }
+            Expression::Range(start, end) => {
+                for id in *start..*end {
+                    self.graph.add_node(id);
+                }
+
+                (*start..*end).collect()
+            }
+            Expression::Connection(connection) => {
+                let items = self.build_items(
+                    std::iter::once(connection.left.as_ref())
+                        .chain(connection.rest.iter().map(|(_, right)| right)),
+                )?;
+
+                for ((_, left), ((_, right), (kind, _))) in items
+                    .iter()
+                    .zip(items[1..].iter().zip(connection.rest.iter()))
+                {
+                    match kind {
+                        ConnectionType::Forward => self.link(left, right),
+                        ConnectionType::Backward => self.link(right, left),
+                        ConnectionType::Both => {
+                            self.link(left, right);
+                            self.link(right, left);
+                        }
+                    }
+                }
+
+                Self::exports(items)
+            }
+            Expression::List(List(items)) => {
+                let items = self.build_items(items.iter())?;
+                Self::exports(items)
+            }
+            Expression::Full(Full(items)) => {
+                let items = self.build_items(items.iter())?;
+
+                for (i, (_, left)) in items.iter().enumerate() {
+                    for (j, (_, right)) in items.iter().enumerate() {
+                        if i != j {
+                            self.link(left, right);
+                        }
+                    }
+                }
+
+                Self::exports(items)
+            }
+            Expression::Cycle(Cycle(items)) => {
+                let items = self.build_items(items.iter())?;
+
+                for (i, (_, left)) in items.iter().enumerate() {
+                    let (_, right) = &items[(i + 1) % items.len()];
+                    self.link(left, right);
+                }
+
+                Self::exports(items)
+            }
+        })
+    }
+}
+
+pub fn build_graph(expressions: &[Expression]) -> Result<Graph, ParseError> {
+    let mut max_number = None;
+    for expression in expressions.iter() {
+        expression.visit_nodes(&mut |node| {
+            if let Node::Number(id) = node {
+                max_number = max_number.max(Some(*id));
+            }
+        });
+    }
+
+    let mut builder = Builder {
+        graph: Graph::new(BTreeMap::new()),
+        names: BTreeMap::new(),
+        next_name: max_number.map_or(Some(0), |max: NodeId| max.checked_add(1)),
+    };
+
+    for expression in expressions.iter() {
+        builder.build(expression)?;
+    }
+
+    Ok(builder.graph)
+}
+
+pub fn parse_graph(input: &str) -> Result<Graph, ParseError> {
+    let (_, expressions) = document(input).map_err(|e| match e {
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            ParseError::Syntax(input.len() - e.input.len())
+        }
+        nom::Err::Incomplete(_) => ParseError::Syntax(input.len()),
+    })?;
+
+    build_graph(&expressions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn two_statement_document() {
+        let graph = parse_graph("1 -> 2\n3 -> 4").unwrap();
+
+        assert_eq!(graph.edges().collect::<Vec<_>>(), vec![(1, 2), (3, 4)]);
+    }
+
+    #[test]
+    fn oversized_number() {
+        assert!(matches!(
+            parse_graph("1 -> 99999999999999999999999"),
+            Err(ParseError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn ranges() {
+        let graph = parse_graph("1..3 -> 9").unwrap();
+
+        assert_eq!(graph.edges().collect::<Vec<_>>(), vec![(1, 9), (2, 9)]);
+    }
+
+    #[test]
+    fn empty_ranges() {
+        assert!(matches!(
+            parse_graph("5..1 -> 9"),
+            Err(ParseError::Syntax(_))
+        ));
+        assert!(matches!(
+            parse_graph("1 -> 3..3"),
+            Err(ParseError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn parse_node() {
+        assert_eq!(Node::parse("\"foo\""), Some(Node::Name("foo".to_string())));
+        assert_eq!(Node::parse(" 7 "), Some(Node::Number(7)));
+        assert_eq!(Node::parse("99999999999999999999999"), None);
+        assert_eq!(Node::parse("7 8"), None);
+    }
+
+    #[test]
+    fn quoted_numbers_are_names() {
+        let graph = parse_graph("\"42\" -> 42").unwrap();
+
+        assert_eq!(graph.node_count(), 2);
+        assert_eq!(graph.from(42).map(|c| c.len()), Some(0));
+        assert_eq!(graph.from(43).map(|c| c.contains(&42)), Some(true));
+    }
+
+    #[test]
+    fn names_after_the_largest_id() {
+        assert_eq!(
+            parse_graph(&format!("{} -> \"a\"", NodeId::MAX)).unwrap_err(),
+            ParseError::NoIdsForNames
+        );
+        assert!(parse_graph(&format!("{} -> 1", NodeId::MAX)).is_ok());
+    }
+
+    #[test]
+    fn comments() {
+        let commented = parse_graph(
+            "# a fixture\n1 -> # the middle\n  2 -> 3 # trailing\n# between\n\"a#b\" -> 1\n#",
+        );
+
+        assert_eq!(commented, parse_graph("1 -> 2 -> 3 \"a#b\" -> 1"));
+        assert_eq!(commented.unwrap().from(4).map(|c| c.len()), Some(1));
+    }
+
+    #[test]
+    fn build_a_handmade_expression() {
+        let node = |id| Exportable::new(Expression::Node(Node::Number(id)), false);
+        let connection = Connection::new(
+            node(1),
+            vec![
+                (ConnectionType::Forward, node(2)),
+                (
+                    ConnectionType::Backward,
+                    Exportable::new(Expression::List(List(vec![node(3), node(4)])), false),
+                ),
+            ],
+        );
+
+        assert_eq!(connection.rest().len(), 2);
+        assert!(!connection.left().is_exported());
+
+        let graph = build_graph(&[Expression::Connection(connection)]).unwrap();
+        assert_eq!(
+            graph.edges().collect::<Vec<_>>(),
+            vec![(1, 2), (3, 2), (4, 2)]
+        );
+    }
+
+    #[test]
+    fn connection_directions() {
+        let edges = |input| parse_graph(input).unwrap().edges().collect::<Vec<_>>();
+
+        assert_eq!(edges("1 -> 2"), vec![(1, 2)]);
+        assert_eq!(edges("1 <- 2"), vec![(2, 1)]);
+        assert_eq!(edges("1 <-> 2"), vec![(1, 2), (2, 1)]);
+        assert_eq!(edges("1 <- 2 -> 3"), vec![(2, 1), (2, 3)]);
+    }
+}
@@ -0,0 +1,18 @@
+use std::process::Command;
+
+// `no_std_check` is its own workspace so it gets the core without the default features, building
+// it is the test
+#[test]
+fn builds_without_std() {
+    let status = Command::new(env!("CARGO"))
+        .args([
+            "build",
+            "--quiet",
+            "--manifest-path",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/no_std_check/Cargo.toml"),
+        ])
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}